@@ -1,10 +1,20 @@
+use crate::ptr::OwningPointer;
+use std::cmp;
 use std::fmt;
 
 pub trait Size: Default {
+    /// Whether the size keeps a count, so that elements moved between
+    /// containers in bulk have to be counted rather than just relinked.
+    const COUNTS: bool;
+
     fn incr(&mut self);
 
     fn decr(&mut self);
 
+    fn add(&mut self, n: usize);
+
+    fn sub(&mut self, n: usize);
+
     fn len<I>(&self, it: I) -> usize
     where
         I: Iterator;
@@ -17,20 +27,85 @@ pub trait Size: Default {
 pub trait LinkAdapter<T> {
     type Link;
     type Size: Size;
+    type Pointer: OwningPointer<T>;
 
     fn link_ref(data: &T) -> &Self::Link;
 
     fn link_mut(data: &mut T) -> &mut Self::Link;
 }
 
+/// Extracts the key an ordered container sorts its elements by.
+pub trait KeyAdapter<T>: LinkAdapter<T> {
+    type Key: ?Sized;
+
+    fn get_key(data: &T) -> &Self::Key;
+}
+
+/// Orders the keys of an ordered container.
+pub trait Compare<K: ?Sized> {
+    fn compare(&self, a: &K, b: &K) -> cmp::Ordering;
+}
+
+/// Compares keys by their `Ord` implementation.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct NaturalOrder;
+
+impl<K> Compare<K> for NaturalOrder
+where
+    K: cmp::Ord + ?Sized,
+{
+    fn compare(&self, a: &K, b: &K) -> cmp::Ordering {
+        a.cmp(b)
+    }
+}
+
+/// A closure compares whole keys only, so a tree ordered by one is searched
+/// with `&Key` and not with a borrowed form of it, such as `&str` for
+/// `String` keys. To accept those too, implement `Compare` for each form on
+/// a named type, as `NaturalOrder` does through `Ord`.
+///
+/// ```compile_fail
+/// use rust_intrusive::adapter::NumerateSize;
+/// use rust_intrusive::bintree::{BinTree, Link};
+/// use rust_intrusive::intrusive_adapter;
+///
+/// struct Z {
+///     name: String,
+///     link: Link<Z>,
+/// }
+///
+/// intrusive_adapter! {
+///     ZLink = Z { link: Link<Z>, key = name: String } with NumerateSize, Box<Z>;
+/// }
+///
+/// let by_len = |a: &String, b: &String| a.len().cmp(&b.len());
+/// let tree = Box::pin(BinTree::with_comparator(ZLink, by_len));
+/// tree.as_ref().get("a");
+/// ```
+impl<K, F> Compare<K> for F
+where
+    K: ?Sized,
+    F: Fn(&K, &K) -> cmp::Ordering,
+{
+    fn compare(&self, a: &K, b: &K) -> cmp::Ordering {
+        self(a, b)
+    }
+}
+
 #[derive(Default)]
 pub struct NumerateSize;
 
 impl Size for NumerateSize {
+    const COUNTS: bool = false;
+
     fn incr(&mut self) {}
 
     fn decr(&mut self) {}
 
+    fn add(&mut self, _: usize) {}
+
+    fn sub(&mut self, _: usize) {}
+
     fn len<I>(&self, it: I) -> usize
     where
         I: Iterator,
@@ -56,6 +131,8 @@ impl fmt::Debug for NumerateSize {
 pub struct LiterateSize(usize);
 
 impl Size for LiterateSize {
+    const COUNTS: bool = true;
+
     fn incr(&mut self) {
         self.0 += 1;
     }
@@ -64,6 +141,14 @@ impl Size for LiterateSize {
         self.0 -= 1;
     }
 
+    fn add(&mut self, n: usize) {
+        self.0 += n;
+    }
+
+    fn sub(&mut self, n: usize) {
+        self.0 -= n;
+    }
+
     fn len<I>(&self, _: I) -> usize
     where
         I: Iterator,
@@ -84,3 +169,125 @@ impl fmt::Debug for LiterateSize {
         write!(f, "{}", self.0)
     }
 }
+
+/// Declares one or more `LinkAdapter`s, each binding a field of `T` to a container.
+///
+/// ```ignore
+/// intrusive_adapter! {
+///     pub XLink = X { link: list::Link<X> } with LiterateSize;
+///     XTreeLink = X { tree_link: bintree::Link<X>, key = data: i32 } with NumerateSize, Box<X>;
+/// }
+/// ```
+///
+/// The optional trailing type is the owning `Pointer` and defaults to `NonNull<T>`.
+/// An adapter for an ordered container can also implement `KeyAdapter`, keyed
+/// either by a field (`key = field: Type`) or by the whole element (`key = self`).
+/// Each adapter is a unit struct deriving `Debug` and `Default`, and also implements
+/// `LinkOffset`, so it can name its field in a `LinkPtr`.
+#[macro_export]
+macro_rules! intrusive_adapter {
+    (@pointer $t:ty) => {
+        ::core::ptr::NonNull<$t>
+    };
+    (@pointer $t:ty, $pointer:ty) => {
+        $pointer
+    };
+    (@key $name:ident $t:ty; self) => {
+        impl $crate::adapter::KeyAdapter<$t> for $name {
+            type Key = $t;
+
+            fn get_key(data: &$t) -> &Self::Key {
+                data
+            }
+        }
+    };
+    (@key $name:ident $t:ty; $key:ident : $key_ty:ty) => {
+        impl $crate::adapter::KeyAdapter<$t> for $name {
+            type Key = $key_ty;
+
+            fn get_key(data: &$t) -> &Self::Key {
+                &data.$key
+            }
+        }
+    };
+    ($($vis:vis $name:ident = $t:ty { $field:ident : $link:ty $(, key = $($key:tt)+)? } with $size:ty $(, $pointer:ty)?);+ $(;)?) => {
+        $(
+            #[derive(Debug, Default)]
+            $vis struct $name;
+
+            impl $crate::adapter::LinkAdapter<$t> for $name {
+                type Link = $link;
+                type Size = $size;
+                type Pointer = $crate::intrusive_adapter!(@pointer $t $(, $pointer)?);
+
+                fn link_ref(data: &$t) -> &Self::Link {
+                    &data.$field
+                }
+
+                fn link_mut(data: &mut $t) -> &mut Self::Link {
+                    &mut data.$field
+                }
+            }
+
+            impl $crate::ptr::LinkOffset<$t> for $name {
+                const OFFSET: usize = ::core::mem::offset_of!($t, $field);
+            }
+
+            $($crate::intrusive_adapter!(@key $name $t; $($key)+);)?
+        )+
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bintree::{self, BinTree};
+    use crate::list::{self, DoublyLinkedList};
+    use std::pin::Pin;
+    use std::ptr::NonNull;
+
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct X {
+        data: i32,
+        link: list::Link<Self>,
+        tree_link: bintree::Link<Self>,
+    }
+
+    impl X {
+        fn new(data: i32) -> Self {
+            X {
+                data,
+                link: list::Link::new(),
+                tree_link: bintree::Link::new(),
+            }
+        }
+    }
+
+    intrusive_adapter! {
+        XLink = X { link: list::Link<X> } with LiterateSize;
+        XTreeLink = X { tree_link: bintree::Link<X>, key = self } with NumerateSize, Box<X>;
+    }
+
+    #[test]
+    fn test_intrusive_adapter() {
+        let mut lst = Box::pin(DoublyLinkedList::new(XLink));
+        let mut tree = Box::pin(BinTree::new(XTreeLink));
+        for data in [2, 3, 1] {
+            let mut item = Box::new(X::new(data));
+            lst.as_mut().push_back(NonNull::from(item.as_mut()));
+            assert!(tree.as_mut().insert(item).is_none());
+        }
+        assert_eq!(lst.as_ref().len(), 3);
+        fn describe<A: Default + fmt::Debug>() -> String {
+            format!("{:?}", A::default())
+        }
+        assert_eq!(describe::<XLink>(), "XLink");
+        let data = |x: Pin<&X>| x.data;
+        assert_eq!(lst.as_ref().iter().map(data).collect::<Vec<_>>(), [2, 3, 1]);
+        assert_eq!(
+            tree.as_ref().iter().map(data).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        lst.as_mut().clear();
+    }
+}
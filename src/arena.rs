@@ -0,0 +1,246 @@
+use crate::ptr::Pointer;
+use std::cell::{Cell, UnsafeCell};
+use std::fmt;
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+use std::mem::{self, MaybeUninit};
+use std::num::NonZeroU32;
+use std::ptr::{self, NonNull};
+
+/// A fixed block of slots that hands out stable addresses and never frees
+/// them one by one; everything allocated is dropped with the arena.
+pub struct Arena<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    len: Cell<usize>,
+}
+
+impl<T> Arena<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity <= u32::MAX as usize);
+        assert_ne!(mem::size_of::<T>(), 0);
+        Self {
+            slots: (0..capacity)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+            len: Cell::new(0),
+        }
+    }
+
+    /// Moves `data` into the next free slot, or hands it back when the arena is full.
+    pub fn alloc(&self, data: T) -> Result<NonNull<T>, T> {
+        let len = self.len.get();
+        if len == self.slots.len() {
+            return Err(data);
+        }
+        let slot = self.slots[len].get().cast::<T>();
+        unsafe { slot.write(data) };
+        self.len.set(len + 1);
+        Ok(unsafe { NonNull::new_unchecked(slot) })
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len.get() == 0
+    }
+
+    fn base(&self) -> *mut T {
+        UnsafeCell::raw_get(self.slots.as_ptr()).cast::<T>()
+    }
+
+    fn index_of(&self, data: NonNull<T>) -> NonZeroU32 {
+        let offset = data.as_ptr().addr().wrapping_sub(self.base().addr());
+        let index = offset / mem::size_of::<T>();
+        debug_assert!(offset.is_multiple_of(mem::size_of::<T>()) && index < self.len.get());
+        NonZeroU32::new(index as u32 + 1).unwrap()
+    }
+
+    fn get(&self, index: NonZeroU32) -> *mut T {
+        let index = index.get() as usize - 1;
+        debug_assert!(index < self.len.get());
+        self.base().wrapping_add(index)
+    }
+}
+
+impl<T> Drop for Arena<T> {
+    fn drop(&mut self) {
+        let len = self.len.get();
+        unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.base(), len)) };
+    }
+}
+
+impl<T> fmt::Debug for Arena<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Arena {{ len: {}, capacity: {} }}",
+            self.len(),
+            self.capacity()
+        )
+    }
+}
+
+/// Names the arena that an `IndexPtr` resolves against.
+pub trait ArenaRef<T: 'static> {
+    fn arena() -> &'static Arena<T>;
+}
+
+/// A pointer kept as a 32-bit slot index into the arena named by `R`.
+#[repr(transparent)]
+pub struct IndexPtr<T, R> {
+    index: NonZeroU32,
+    _marker: PhantomData<(*mut T, R)>,
+}
+
+impl<T: 'static, R> Pointer<T> for IndexPtr<T, R>
+where
+    R: ArenaRef<T>,
+{
+    fn from_raw(raw_ptr: NonNull<T>, _: usize) -> Self {
+        Self {
+            index: R::arena().index_of(raw_ptr),
+            _marker: PhantomData,
+        }
+    }
+
+    fn as_ref(&self) -> &T {
+        unsafe { &*R::arena().get(self.index) }
+    }
+
+    fn as_mut(&mut self) -> &mut T {
+        unsafe { &mut *R::arena().get(self.index) }
+    }
+}
+
+impl<T, R> fmt::Debug for IndexPtr<T, R> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "#{}", self.index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::adapter::NumerateSize;
+    use crate::bintree::{self, BinTree};
+    use crate::intrusive_adapter;
+    use crate::list::{self, DoublyLinkedList};
+    use crate::slist::{self, SinglyLinkedList};
+    use std::pin::Pin;
+
+    type Ptr = IndexPtr<X, XArena>;
+
+    struct X {
+        data: i32,
+        list_link: list::Link<Self, Ptr>,
+        slist_link: slist::Link<Self, Ptr>,
+        tree_link: bintree::Link<Self, Ptr>,
+    }
+
+    impl X {
+        fn new(data: i32) -> NonNull<Self> {
+            let item = X {
+                data,
+                list_link: list::Link::new(),
+                slist_link: slist::Link::new(),
+                tree_link: bintree::Link::new(),
+            };
+            XArena::arena().alloc(item).ok().unwrap()
+        }
+    }
+
+    struct XArena;
+
+    impl ArenaRef<X> for XArena {
+        fn arena() -> &'static Arena<X> {
+            thread_local! {
+                static ARENA: &'static Arena<X> = Box::leak(Box::new(Arena::with_capacity(16)));
+            }
+            ARENA.with(|arena| *arena)
+        }
+    }
+
+    intrusive_adapter! {
+        XListLink = X { list_link: list::Link<X, Ptr> } with NumerateSize;
+        XSListLink = X { slist_link: slist::Link<X, Ptr> } with NumerateSize;
+        XTreeLink = X { tree_link: bintree::Link<X, Ptr>, key = data: i32 } with NumerateSize;
+    }
+
+    #[test]
+    fn test_index_ptr_size() {
+        assert_eq!(mem::size_of::<Option<Ptr>>(), 4);
+        assert_eq!(mem::size_of::<list::Link<X, Ptr>>(), 8);
+        assert_eq!(mem::size_of::<slist::Link<X, Ptr>>(), 4);
+        assert_eq!(mem::size_of::<bintree::Link<X, Ptr>>(), 12);
+    }
+
+    #[test]
+    fn test_arena() {
+        let arena = Arena::with_capacity(2);
+        assert!(arena.is_empty());
+        let a = arena.alloc(String::from("a")).unwrap();
+        let b = arena.alloc(String::from("b")).unwrap();
+        assert_eq!(arena.alloc(String::from("c")), Err(String::from("c")));
+        assert_eq!(arena.len(), 2);
+        assert_eq!(arena.index_of(a).get(), 1);
+        assert_eq!(arena.index_of(b).get(), 2);
+        assert_eq!(unsafe { &*arena.get(arena.index_of(b)) }, "b");
+    }
+
+    #[test]
+    fn test_index_ptr_containers() {
+        let mut lst = Box::pin(DoublyLinkedList::new(XListLink));
+        let mut slst = Box::pin(SinglyLinkedList::new(XSListLink));
+        let mut tree = Box::pin(BinTree::new(XTreeLink));
+        for data in [3, 1, 4, 5, 2] {
+            let item = X::new(data);
+            lst.as_mut().push_back(item);
+            slst.as_mut().push_front(item);
+            assert!(tree.as_mut().insert(item).is_none());
+        }
+        assert_eq!(XArena::arena().len(), 5);
+
+        let data = |it: &mut dyn Iterator<Item = &X>| it.map(|x| x.data).collect::<Vec<_>>();
+        assert_eq!(
+            data(&mut lst.as_ref().iter().map(Pin::get_ref)),
+            [3, 1, 4, 5, 2]
+        );
+        assert_eq!(
+            data(&mut lst.as_ref().iter().rev().map(Pin::get_ref)),
+            [2, 5, 4, 1, 3]
+        );
+        assert_eq!(
+            data(&mut slst.as_ref().iter().map(Pin::get_ref)),
+            [2, 5, 4, 1, 3]
+        );
+        assert_eq!(
+            data(&mut tree.as_ref().iter().map(Pin::get_ref)),
+            [1, 2, 3, 4, 5]
+        );
+
+        let item = lst.as_mut().pop_front().unwrap();
+        assert_eq!(unsafe { item.as_ref() }.data, 3);
+        assert_eq!(
+            unsafe { tree.as_mut().pop_back().unwrap().as_ref() }.data,
+            5
+        );
+        assert_eq!(
+            unsafe { slst.as_mut().pop_front().unwrap().as_ref() }.data,
+            2
+        );
+        assert_eq!(
+            data(&mut lst.as_ref().iter().map(Pin::get_ref)),
+            [1, 4, 5, 2]
+        );
+        assert_eq!(
+            data(&mut tree.as_ref().iter().rev().map(Pin::get_ref)),
+            [4, 3, 2, 1]
+        );
+    }
+}
@@ -1,45 +1,1334 @@
-use super::Adapter;
-use crate::ptr::{NonNullPtr, Pointer};
-use std::marker::PhantomData;
+use crate::adapter::{KeyAdapter, LinkAdapter, NaturalOrder, Size};
+use crate::bintree::{self, AsTreeLink, Balance, TreeLink};
+use crate::ptr::{OwningPointer, Pointer, UniquePointer};
+use std::borrow::Borrow;
+use std::cell::Cell;
+use std::cmp;
+use std::fmt;
+use std::mem;
+use std::ops::Bound;
 use std::pin::Pin;
 use std::ptr::NonNull;
 
-pub struct Link<T, P>
+pub type Iter<'a, T, A, P> = bintree::Iter<'a, T, TreeLink<A>, P>;
+
+pub type IterMut<'a, T, A, P> = bintree::IterMut<'a, T, TreeLink<A>, P>;
+
+pub type Range<'a, T, A, P> = bintree::Range<'a, T, TreeLink<A>, P>;
+
+pub type RangeMut<'a, T, A, P> = bintree::RangeMut<'a, T, TreeLink<A>, P>;
+
+pub type Union<'a, T, A, P> = bintree::Union<'a, T, TreeLink<A>, P, NaturalOrder>;
+
+pub type Intersection<'a, T, A, P> = bintree::Intersection<'a, T, TreeLink<A>, P, NaturalOrder>;
+
+pub type Difference<'a, T, A, P> = bintree::Difference<'a, T, TreeLink<A>, P, NaturalOrder>;
+
+pub type SymmetricDifference<'a, T, A, P> =
+    bintree::SymmetricDifference<'a, T, TreeLink<A>, P, NaturalOrder>;
+
+/// A `bintree::Link` that also carries the height of the subtree below its
+/// node, which is zero while the node is in no tree.
+#[repr(C)]
+pub struct Link<T, P = NonNull<T>> {
+    tree: bintree::Link<T, P>,
+    height: Cell<usize>,
+}
+
+impl<T, P> Link<T, P> {
+    pub const fn new() -> Self {
+        Self {
+            tree: bintree::Link::new(),
+            height: Cell::new(0),
+        }
+    }
+
+    pub const fn is_linked(&self) -> bool {
+        unsafe { *self.height.as_ptr() != 0 }
+    }
+}
+
+impl<T, P> AsTreeLink<T> for Link<T, P> {
+    type Pointer = P;
+
+    fn tree_ref<A>(data: &T) -> &bintree::Link<T, P>
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        &A::link_ref(data).tree
+    }
+
+    fn tree_mut<A>(data: &mut T) -> &mut bintree::Link<T, P>
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        &mut A::link_mut(data).tree
+    }
+}
+
+impl<T, P> Link<T, P>
 where
     T: Unpin,
     P: Pointer<T>,
 {
-    height: usize,
-    left_ptr: Option<Pin<NonNullPtr<T, P>>>,
-    right_ptr: Option<Pin<NonNullPtr<T, P>>>,
+    fn of<'a, A>(node: NonNull<T>) -> &'a Self
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        A::link_ref(unsafe { &*node.as_ptr() })
+    }
+
+    fn tree<'a, A>(node: NonNull<T>) -> &'a bintree::Link<T, P>
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        bintree::Link::of::<TreeLink<A>>(node)
+    }
+
+    fn height_of<A>(node: Option<NonNull<T>>) -> usize
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        node.map_or(0, |node| Self::of::<A>(node).height.get())
+    }
+
+    fn balance_of<A>(node: NonNull<T>) -> isize
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        let link = Self::tree::<A>(node);
+        Self::height_of::<A>(link.left()) as isize - Self::height_of::<A>(link.right()) as isize
+    }
+
+    fn update_height<A>(node: NonNull<T>)
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        let link = Self::tree::<A>(node);
+        let height = 1 + cmp::max(
+            Self::height_of::<A>(link.left()),
+            Self::height_of::<A>(link.right()),
+        );
+        Self::of::<A>(node).height.set(height);
+    }
+
+    fn rotate_left<A>(tree: &mut bintree::Link<T, P>, node: NonNull<T>) -> NonNull<T>
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        tree.rotate_left::<TreeLink<A>>(node);
+        let top = Self::tree::<A>(node).top().unwrap();
+        Self::update_height::<A>(node);
+        Self::update_height::<A>(top);
+        top
+    }
+
+    fn rotate_right<A>(tree: &mut bintree::Link<T, P>, node: NonNull<T>) -> NonNull<T>
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        tree.rotate_right::<TreeLink<A>>(node);
+        let top = Self::tree::<A>(node).top().unwrap();
+        Self::update_height::<A>(node);
+        Self::update_height::<A>(top);
+        top
+    }
+
+    /// Restores the AVL invariant at `node` and returns the root of its subtree.
+    fn rebalance<A>(tree: &mut bintree::Link<T, P>, node: NonNull<T>) -> NonNull<T>
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        Self::update_height::<A>(node);
+        let balance = Self::balance_of::<A>(node);
+        if balance > 1 {
+            let left = Self::tree::<A>(node).left().unwrap();
+            if Self::balance_of::<A>(left) < 0 {
+                Self::rotate_left::<A>(tree, left);
+            }
+            Self::rotate_right::<A>(tree, node)
+        } else if balance < -1 {
+            let right = Self::tree::<A>(node).right().unwrap();
+            if Self::balance_of::<A>(right) > 0 {
+                Self::rotate_right::<A>(tree, right);
+            }
+            Self::rotate_left::<A>(tree, node)
+        } else {
+            node
+        }
+    }
+
+    /// Walks from `node` up to the root, stopping as soon as a subtree keeps its height.
+    fn retrace<A>(tree: &mut bintree::Link<T, P>, mut node: Option<NonNull<T>>)
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        while let Some(item) = node {
+            let height = Self::of::<A>(item).height.get();
+            let item = Self::rebalance::<A>(tree, item);
+            if Self::of::<A>(item).height.get() == height {
+                break;
+            }
+            node = Self::tree::<A>(item).top();
+        }
+    }
+
+    /// Links `node` as a leaf at `slot` and rebalances the path above it.
+    fn link_leaf<A>(tree: &mut bintree::Link<T, P>, slot: &bintree::Slot<T>, node: NonNull<T>)
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        tree.link_leaf::<TreeLink<A>>(slot, node);
+        Self::of::<A>(node).height.set(1);
+        Self::retrace::<A>(tree, slot.top);
+    }
+
+    /// Unlinks `node` and rebalances the path above the place that lost a node.
+    fn unlink_node<A>(tree: &mut bintree::Link<T, P>, node: NonNull<T>)
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        let height = Self::of::<A>(node).height.replace(0);
+        let vacancy = tree.unlink_node::<TreeLink<A>>(node);
+        // a spliced successor starts from the height of the place it took
+        if let Some(next) = vacancy.next {
+            Self::of::<A>(next).height.set(height);
+        }
+        Self::retrace::<A>(tree, vacancy.top);
+    }
+
+    /// Unlinks every node below the header, releasing the owned ones.
+    fn clear<A>(tree: &mut bintree::Link<T, P>)
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        tree.drain::<TreeLink<A>>(|node| {
+            Self::of::<A>(node).height.set(0);
+            drop(unsafe { A::Pointer::from_raw(node) });
+        });
+    }
+}
+
+impl<T, P> Default for Link<T, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, P> Unpin for Link<T, P> where T: Unpin {}
+
+impl<T, P> cmp::PartialEq for Link<T, P> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<T, P> cmp::Eq for Link<T, P> {}
+
+impl<T, P> cmp::PartialOrd for Link<T, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
+impl<T, P> cmp::Ord for Link<T, P> {
+    fn cmp(&self, _: &Self) -> cmp::Ordering {
+        cmp::Ordering::Equal
+    }
+}
+
+impl<T, P> fmt::Debug for Link<T, P>
+where
+    P: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} height: {}", self.tree, self.height.get())
+    }
+}
+
+#[derive(Debug)]
 pub struct AVLTree<T, A, P = NonNull<T>>
 where
     T: Unpin,
     P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
 {
-    link: Link<T, P>,
-    size: A,
+    size: A::Size,
+    link: bintree::Link<T, P>,
 }
 
 impl<T, A, P> AVLTree<T, A, P>
 where
-    T: Unpin + Ord,
+    T: Unpin,
     P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
 {
-    pub fn insert(self: Pin<&mut Self>, data: NonNull<T>) -> bool
+    pub fn new(_: A) -> Self {
+        Self {
+            size: Default::default(),
+            link: bintree::Link::new(),
+        }
+    }
+}
+
+impl<T, A, P> AVLTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    A::Key: cmp::Ord,
+{
+    pub fn get<Q>(self: Pin<&Self>, key: &Q) -> Option<Pin<&T>>
+    where
+        A::Key: Borrow<Q>,
+        Q: cmp::Ord + ?Sized,
+    {
+        let self_ = Pin::get_ref(self);
+        let node = self_
+            .link
+            .search::<TreeLink<A>, Q, NaturalOrder>(key, &NaturalOrder)
+            .ok()?;
+        Some(Pin::new(unsafe { &*node.as_ptr() }))
+    }
+
+    pub fn get_mut<Q>(self: Pin<&mut Self>, key: &Q) -> Option<Pin<&mut T>>
+    where
+        A::Key: Borrow<Q>,
+        Q: cmp::Ord + ?Sized,
+        A::Pointer: UniquePointer<T>,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_
+            .link
+            .search::<TreeLink<A>, Q, NaturalOrder>(key, &NaturalOrder)
+            .ok()?;
+        Some(Pin::new(unsafe { &mut *node.as_ptr() }))
+    }
+
+    pub fn insert(self: Pin<&mut Self>, item: A::Pointer) -> Option<A::Pointer> {
+        let item = item.into_raw();
+        assert!(
+            !Link::of::<A>(item).is_linked(),
+            "the element is already linked"
+        );
+
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let key = A::get_key(unsafe { item.as_ref() });
+        match self_
+            .link
+            .search::<TreeLink<A>, A::Key, NaturalOrder>(key, &NaturalOrder)
+        {
+            Ok(_) => Some(unsafe { A::Pointer::from_raw(item) }),
+            Err(slot) => {
+                Link::link_leaf::<A>(&mut self_.link, &slot, item);
+                self_.size.incr();
+                None
+            }
+        }
+    }
+
+    pub fn remove<Q>(self: Pin<&mut Self>, key: &Q) -> Option<A::Pointer>
+    where
+        A::Key: Borrow<Q>,
+        Q: cmp::Ord + ?Sized,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_
+            .link
+            .search::<TreeLink<A>, Q, NaturalOrder>(key, &NaturalOrder)
+            .ok()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn union<'a>(self: Pin<&'a Self>, other: Pin<&'a Self>) -> Union<'a, T, A, P> {
+        bintree::Union(self.merge(other))
+    }
+
+    pub fn intersection<'a>(
+        self: Pin<&'a Self>,
+        other: Pin<&'a Self>,
+    ) -> Intersection<'a, T, A, P> {
+        bintree::Intersection(self.merge(other))
+    }
+
+    pub fn difference<'a>(self: Pin<&'a Self>, other: Pin<&'a Self>) -> Difference<'a, T, A, P> {
+        bintree::Difference(self.merge(other))
+    }
+
+    pub fn symmetric_difference<'a>(
+        self: Pin<&'a Self>,
+        other: Pin<&'a Self>,
+    ) -> SymmetricDifference<'a, T, A, P> {
+        bintree::SymmetricDifference(self.merge(other))
+    }
+
+    fn merge<'a>(
+        self: Pin<&'a Self>,
+        other: Pin<&'a Self>,
+    ) -> bintree::Merge<'a, T, TreeLink<A>, P, NaturalOrder> {
+        bintree::Merge::new(self.iter(), other.iter(), &NaturalOrder)
+    }
+
+    /// Moves the elements of `other` whose keys are missing here into this tree.
+    ///
+    /// Afterwards `other` holds only the elements whose keys both trees held.
+    pub fn union_with(self: Pin<&mut Self>, other: Pin<&mut Self>) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let other = unsafe { Pin::get_unchecked_mut(other) };
+        self_.link.union_with::<TreeLink<A>, NaturalOrder, Self>(
+            &mut self_.size,
+            &mut other.link,
+            &mut other.size,
+            &NaturalOrder,
+        );
+    }
+
+    /// Moves the elements whose keys `other` also holds into the empty tree `into`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `into` is not empty.
+    pub fn extract_intersection(self: Pin<&mut Self>, other: Pin<&Self>, into: Pin<&mut Self>) {
+        self.extract(other, into, true)
+    }
+
+    /// Moves the elements whose keys `other` lacks into the empty tree `into`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `into` is not empty.
+    pub fn extract_difference(self: Pin<&mut Self>, other: Pin<&Self>, into: Pin<&mut Self>) {
+        self.extract(other, into, false)
+    }
+
+    fn extract(self: Pin<&mut Self>, other: Pin<&Self>, into: Pin<&mut Self>, shared: bool) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let other = Pin::get_ref(other);
+        let into = unsafe { Pin::get_unchecked_mut(into) };
+        self_.link.extract::<TreeLink<A>, NaturalOrder, Self>(
+            &mut self_.size,
+            &other.link,
+            &NaturalOrder,
+            &mut into.link,
+            &mut into.size,
+            shared,
+        );
+    }
+}
+
+impl<T, A, P> AVLTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn unlink(&mut self, node: NonNull<T>) -> A::Pointer {
+        Link::unlink_node::<A>(&mut self.link, node);
+        self.size.decr();
+        unsafe { A::Pointer::from_raw(node) }
+    }
+
+    /// Unlinks every element, releasing the owned ones.
+    pub fn clear(self: Pin<&mut Self>) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        Link::clear::<A>(&mut self_.link);
+        self_.size = Default::default();
+    }
+
+    pub fn pop_front(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.left()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn pop_back(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.right()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn front(self: Pin<&Self>) -> Option<&T> {
+        Pin::get_ref(self).link.front()
+    }
+
+    pub fn front_mut(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
     {
-	false
+        unsafe { Pin::get_unchecked_mut(self) }.link.front_mut()
+    }
+
+    pub fn back(self: Pin<&Self>) -> Option<&T> {
+        Pin::get_ref(self).link.back()
+    }
+
+    pub fn back_mut(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.back_mut()
+    }
+
+    pub fn iter(self: Pin<&Self>) -> Iter<'_, T, A, P> {
+        Pin::get_ref(self).link.iter()
+    }
+
+    pub fn iter_mut(self: Pin<&mut Self>) -> IterMut<'_, T, A, P>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.iter_mut()
+    }
+
+    pub fn len(self: Pin<&Self>) -> usize {
+        self.link.len::<TreeLink<A>>(&self.size)
+    }
+
+    pub fn is_empty(self: Pin<&Self>) -> bool {
+        self.link.is_empty::<TreeLink<A>>(&self.size)
+    }
+}
+
+impl<T, A, P> Default for AVLTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>> + Default,
+{
+    fn default() -> Self {
+        Self::new(A::default())
+    }
+}
+
+impl<T, A, P> Balance<T, P> for AVLTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn link_leaf(tree: &mut bintree::Link<T, P>, slot: &bintree::Slot<T>, node: NonNull<T>) {
+        Link::link_leaf::<A>(tree, slot, node);
+    }
+
+    fn unlink_node(tree: &mut bintree::Link<T, P>, node: NonNull<T>) {
+        Link::unlink_node::<A>(tree, node);
+    }
+}
+
+impl<T, A, P> Drop for AVLTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn drop(&mut self) {
+        if mem::needs_drop::<A::Pointer>() {
+            unsafe { Pin::new_unchecked(self) }.clear();
+        }
     }
 }
 
+impl<T, A, P> Unpin for AVLTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T> + Unpin,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+}
+
+#[derive(Debug)]
 pub struct AVLMultiTree<T, A, P = NonNull<T>>
 where
     T: Unpin,
     P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    size: A::Size,
+    link: bintree::Link<T, P>,
+}
+
+impl<T, A, P> AVLMultiTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    pub fn new(_: A) -> Self {
+        Self {
+            size: Default::default(),
+            link: bintree::Link::new(),
+        }
+    }
+}
+
+impl<T, A, P> AVLMultiTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    A::Key: cmp::Ord,
+{
+    /// Returns the first of the elements whose key equals `key`.
+    pub fn get<Q>(self: Pin<&Self>, key: &Q) -> Option<Pin<&T>>
+    where
+        A::Key: Borrow<Q>,
+        Q: cmp::Ord + ?Sized,
+    {
+        self.equal_range(key).next()
+    }
+
+    /// Returns the first of the elements whose key equals `key`.
+    pub fn get_mut<Q>(self: Pin<&mut Self>, key: &Q) -> Option<Pin<&mut T>>
+    where
+        A::Key: Borrow<Q>,
+        Q: cmp::Ord + ?Sized,
+        A::Pointer: UniquePointer<T>,
+    {
+        self.equal_range_mut(key).next()
+    }
+
+    /// Inserts `item` after all the elements with an equal key.
+    pub fn insert(self: Pin<&mut Self>, item: A::Pointer) {
+        let item = item.into_raw();
+        assert!(
+            !Link::of::<A>(item).is_linked(),
+            "the element is already linked"
+        );
+
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let key = A::get_key(unsafe { item.as_ref() });
+        // an equal key orders as greater, so the search always ends past the equal run
+        let after_equal = |a: &A::Key, b: &A::Key| {
+            if a < b {
+                cmp::Ordering::Less
+            } else {
+                cmp::Ordering::Greater
+            }
+        };
+        if let Err(slot) = self_
+            .link
+            .search::<TreeLink<A>, A::Key, _>(key, &after_equal)
+        {
+            Link::link_leaf::<A>(&mut self_.link, &slot, item);
+            self_.size.incr();
+        }
+    }
+
+    /// Removes `data` itself, leaving the other elements with its key in place.
+    pub fn remove(self: Pin<&mut Self>, data: &T) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let key = A::get_key(data);
+        let mut node = self_
+            .link
+            .lower_node::<TreeLink<A>, A::Key, NaturalOrder>(Bound::Included(key), &NaturalOrder);
+        while let Some(item) = node {
+            if std::ptr::eq(item.as_ptr(), data) {
+                return Some(self_.unlink(item));
+            } else if A::get_key(unsafe { item.as_ref() }) != key {
+                break;
+            }
+            node = bintree::Link::next_of::<TreeLink<A>>(item);
+        }
+        None
+    }
+
+    /// Iterates from the first element whose key is not less than `key` to the back.
+    pub fn lower_bound<Q>(self: Pin<&Self>, key: &Q) -> Range<'_, T, A, P>
+    where
+        A::Key: Borrow<Q>,
+        Q: cmp::Ord + ?Sized,
+    {
+        Pin::get_ref(self)
+            .link
+            .range::<TreeLink<A>, Q, NaturalOrder, _>(
+                (Bound::Included(key), Bound::Unbounded),
+                &NaturalOrder,
+            )
+    }
+
+    /// Iterates from the first element whose key is greater than `key` to the back.
+    pub fn upper_bound<Q>(self: Pin<&Self>, key: &Q) -> Range<'_, T, A, P>
+    where
+        A::Key: Borrow<Q>,
+        Q: cmp::Ord + ?Sized,
+    {
+        Pin::get_ref(self)
+            .link
+            .range::<TreeLink<A>, Q, NaturalOrder, _>(
+                (Bound::Excluded(key), Bound::Unbounded),
+                &NaturalOrder,
+            )
+    }
+
+    /// Iterates over the elements whose key equals `key` in insertion order.
+    pub fn equal_range<Q>(self: Pin<&Self>, key: &Q) -> Range<'_, T, A, P>
+    where
+        A::Key: Borrow<Q>,
+        Q: cmp::Ord + ?Sized,
+    {
+        Pin::get_ref(self)
+            .link
+            .range::<TreeLink<A>, Q, NaturalOrder, _>(
+                (Bound::Included(key), Bound::Included(key)),
+                &NaturalOrder,
+            )
+    }
+
+    pub fn equal_range_mut<Q>(self: Pin<&mut Self>, key: &Q) -> RangeMut<'_, T, A, P>
+    where
+        A::Key: Borrow<Q>,
+        Q: cmp::Ord + ?Sized,
+        A::Pointer: UniquePointer<T>,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        self_.link.range_mut::<TreeLink<A>, Q, NaturalOrder, _>(
+            (Bound::Included(key), Bound::Included(key)),
+            &NaturalOrder,
+        )
+    }
+
+    pub fn count<Q>(self: Pin<&Self>, key: &Q) -> usize
+    where
+        A::Key: Borrow<Q>,
+        Q: cmp::Ord + ?Sized,
+    {
+        self.equal_range(key).count()
+    }
+}
+
+impl<T, A, P> AVLMultiTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn unlink(&mut self, node: NonNull<T>) -> A::Pointer {
+        Link::unlink_node::<A>(&mut self.link, node);
+        self.size.decr();
+        unsafe { A::Pointer::from_raw(node) }
+    }
+
+    /// Unlinks every element, releasing the owned ones.
+    pub fn clear(self: Pin<&mut Self>) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        Link::clear::<A>(&mut self_.link);
+        self_.size = Default::default();
+    }
+
+    pub fn pop_front(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.left()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn pop_back(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.right()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn front(self: Pin<&Self>) -> Option<&T> {
+        Pin::get_ref(self).link.front()
+    }
+
+    pub fn front_mut(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.front_mut()
+    }
+
+    pub fn back(self: Pin<&Self>) -> Option<&T> {
+        Pin::get_ref(self).link.back()
+    }
+
+    pub fn back_mut(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.back_mut()
+    }
+
+    pub fn iter(self: Pin<&Self>) -> Iter<'_, T, A, P> {
+        Pin::get_ref(self).link.iter()
+    }
+
+    pub fn iter_mut(self: Pin<&mut Self>) -> IterMut<'_, T, A, P>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.iter_mut()
+    }
+
+    pub fn len(self: Pin<&Self>) -> usize {
+        self.link.len::<TreeLink<A>>(&self.size)
+    }
+
+    pub fn is_empty(self: Pin<&Self>) -> bool {
+        self.link.is_empty::<TreeLink<A>>(&self.size)
+    }
+}
+
+impl<T, A, P> Default for AVLMultiTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>> + Default,
+{
+    fn default() -> Self {
+        Self::new(A::default())
+    }
+}
+
+impl<T, A, P> Drop for AVLMultiTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
 {
-    link: Link<T, P>,
-    size: A,
+    fn drop(&mut self) {
+        if mem::needs_drop::<A::Pointer>() {
+            unsafe { Pin::new_unchecked(self) }.clear();
+        }
+    }
 }
 
+impl<T, A, P> Unpin for AVLMultiTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T> + Unpin,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::adapter::{LiterateSize, NumerateSize};
+    use crate::intrusive_adapter;
+    use std::fmt::Formatter;
+    use std::rc::Rc;
+
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct X {
+        data: i32,
+        link: Link<Self>,
+    }
+
+    impl X {
+        fn new(data: i32) -> NonNull<Self> {
+            let ptr = Box::new(X {
+                data,
+                link: Link::new(),
+            });
+            let ptr = Box::into_raw(ptr);
+            NonNull::new(ptr).unwrap()
+        }
+
+        fn from(data: Option<NonNull<Self>>) -> Option<Box<Self>> {
+            if let Some(data) = data {
+                let ptr = unsafe { Box::from_raw(data.as_ptr()) };
+                assert!(!ptr.link.is_linked());
+                Some(ptr)
+            } else {
+                None
+            }
+        }
+    }
+
+    impl fmt::Debug for X {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "X ({:p}) {{ data: {:?}, link: {:?} }}",
+                self, self.data, self.link
+            )
+        }
+    }
+
+    intrusive_adapter! {
+        XLink = X { link: Link<X>, key = self } with NumerateSize;
+    }
+
+    intrusive_adapter! {
+        XSizeLink = X { link: Link<X>, key = self } with LiterateSize;
+    }
+
+    // checks parent links, heights and balance factors, and returns the height of the subtree
+    fn check<T, A>(node: Option<NonNull<T>>, top: Option<NonNull<T>>) -> usize
+    where
+        T: Unpin,
+        A: LinkAdapter<T, Link = Link<T>>,
+    {
+        if let Some(node) = node {
+            let link = Link::of::<A>(node);
+            assert_eq!(link.tree.top(), top);
+            let left = check::<T, A>(link.tree.left(), Some(node));
+            let right = check::<T, A>(link.tree.right(), Some(node));
+            assert!(left.abs_diff(right) <= 1);
+            assert_eq!(link.height.get(), 1 + cmp::max(left, right));
+            link.height.get()
+        } else {
+            0
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut tree = Box::pin(AVLTree::new(XLink));
+        // []
+        assert_eq!(tree.as_ref().len(), 0);
+        assert!(tree.as_ref().is_empty());
+        assert_eq!(tree.as_ref().front(), None);
+        assert_eq!(tree.as_ref().back(), None);
+        assert_eq!(tree.as_ref().iter().next(), None);
+        assert_eq!(tree.as_mut().pop_front(), None);
+        assert_eq!(tree.as_mut().pop_back(), None);
+    }
+
+    #[test]
+    fn test_insert_sorted() {
+        let mut tree = Box::pin(AVLTree::new(XSizeLink));
+        for i in 0..1000 {
+            assert_eq!(tree.as_mut().insert(X::new(i)), None);
+        }
+        // [0..1000]
+        let height = check::<X, XSizeLink>(tree.link.top(), None);
+        assert!(height <= 15, "height = {}", height);
+        assert_eq!(tree.as_ref().len(), 1000);
+        assert_eq!(tree.as_ref().front().unwrap().data, 0);
+        assert_eq!(tree.as_ref().back().unwrap().data, 999);
+        assert!(tree.as_ref().iter().map(|x| x.data).eq(0..1000));
+        assert!(tree
+            .as_ref()
+            .iter()
+            .rev()
+            .map(|x| x.data)
+            .eq((0..1000).rev()));
+
+        let dup = X::new(500);
+        assert_eq!(tree.as_mut().insert(dup), Some(dup));
+        X::from(Some(dup));
+
+        while let Some(item) = X::from(tree.as_mut().pop_front()) {
+            assert_eq!(item.data, 1000 - tree.as_ref().len() as i32 - 1);
+            check::<X, XSizeLink>(tree.link.top(), None);
+        }
+        assert!(tree.as_ref().is_empty());
+    }
+
+    #[test]
+    fn test_get_remove() {
+        let mut tree = Box::pin(AVLTree::new(XLink));
+        for i in [5, 3, 8, 1, 4, 7, 9, 2, 6] {
+            tree.as_mut().insert(X::new(i));
+        }
+        // [1-2-3-4-5-6-7-8-9]
+        check::<X, XLink>(tree.link.top(), None);
+        let key = X {
+            data: 4,
+            link: Link::new(),
+        };
+        assert_eq!(tree.as_ref().get(&key).unwrap().data, 4);
+        AVLTree::get_mut(tree.as_mut(), &key).unwrap().data = 4;
+
+        // two children
+        let item = X::from(tree.as_mut().remove(&X {
+            data: 5,
+            link: Link::new(),
+        }))
+        .unwrap();
+        assert_eq!(item.data, 5);
+        check::<X, XLink>(tree.link.top(), None);
+        // leaf
+        let item = X::from(tree.as_mut().remove(&X {
+            data: 2,
+            link: Link::new(),
+        }))
+        .unwrap();
+        assert_eq!(item.data, 2);
+        check::<X, XLink>(tree.link.top(), None);
+        // missing
+        assert_eq!(
+            tree.as_mut().remove(&X {
+                data: 5,
+                link: Link::new()
+            }),
+            None
+        );
+        assert_eq!(
+            tree.as_ref().get(&X {
+                data: 5,
+                link: Link::new()
+            }),
+            None
+        );
+        // [1-3-4-6-7-8-9]
+        assert!(tree
+            .as_ref()
+            .iter()
+            .map(|x| x.data)
+            .eq([1, 3, 4, 6, 7, 8, 9]));
+
+        let item = X::from(tree.as_mut().remove(&X {
+            data: 9,
+            link: Link::new(),
+        }))
+        .unwrap();
+        assert_eq!(item.data, 9);
+        assert_eq!(tree.as_ref().back().unwrap().data, 8);
+        let item = X::from(tree.as_mut().remove(&X {
+            data: 1,
+            link: Link::new(),
+        }))
+        .unwrap();
+        assert_eq!(item.data, 1);
+        assert_eq!(tree.as_ref().front().unwrap().data, 3);
+        check::<X, XLink>(tree.link.top(), None);
+
+        while X::from(tree.as_mut().pop_back()).is_some() {
+            check::<X, XLink>(tree.link.top(), None);
+        }
+        assert_eq!(tree.as_ref().len(), 0);
+    }
+
+    #[test]
+    fn test_iter_double_ended() {
+        let mut tree = Box::pin(AVLTree::new(XLink));
+        for i in 1..=5 {
+            tree.as_mut().insert(X::new(i));
+        }
+        // [1-2-3-4-5]
+        let mut it = tree.as_ref().iter();
+        assert_eq!(it.next().unwrap().data, 1);
+        assert_eq!(it.next_back().unwrap().data, 5);
+        assert_eq!(it.next().unwrap().data, 2);
+        assert_eq!(it.next_back().unwrap().data, 4);
+        assert_eq!(it.next().unwrap().data, 3);
+        assert_eq!(it.next_back(), None);
+        assert_eq!(it.next(), None);
+
+        for mut x in tree.as_mut().iter_mut().rev() {
+            x.data *= 10;
+        }
+        assert!(tree
+            .as_ref()
+            .iter()
+            .map(|x| x.data)
+            .eq([10, 20, 30, 40, 50]));
+        while X::from(tree.as_mut().pop_back()).is_some() {}
+    }
+
+    #[derive(Debug)]
+    struct Y {
+        key: i32,
+        id: i32,
+        link: Link<Self>,
+    }
+
+    impl Y {
+        fn new(key: i32, id: i32) -> NonNull<Self> {
+            let ptr = Box::new(Y {
+                key,
+                id,
+                link: Link::new(),
+            });
+            NonNull::new(Box::into_raw(ptr)).unwrap()
+        }
+
+        fn from(data: Option<NonNull<Self>>) -> Option<Box<Self>> {
+            let ptr = unsafe { Box::from_raw(data?.as_ptr()) };
+            assert!(!ptr.link.is_linked());
+            Some(ptr)
+        }
+    }
+
+    intrusive_adapter! {
+        YLink = Y { link: Link<Y>, key = key: i32 } with LiterateSize;
+    }
+
+    #[test]
+    fn test_multi_equal_range() {
+        let mut tree = Box::pin(AVLMultiTree::new(YLink));
+        for id in 0..30 {
+            tree.as_mut().insert(Y::new(id % 3, id));
+        }
+        // [0,0,..,1,1,..,2,2,..]
+        check::<Y, YLink>(tree.link.top(), None);
+        assert_eq!(tree.as_ref().len(), 30);
+        assert_eq!(tree.as_ref().count(&1), 10);
+        assert_eq!(tree.as_ref().count(&3), 0);
+        assert!(tree
+            .as_ref()
+            .equal_range(&1)
+            .map(|y| y.id)
+            .eq((1..30).step_by(3)));
+        assert!(tree
+            .as_ref()
+            .equal_range(&2)
+            .rev()
+            .map(|y| y.id)
+            .eq((2..30).step_by(3).rev()));
+        assert!(tree.as_ref().equal_range(&-1).next().is_none());
+        assert_eq!(tree.as_ref().get(&2).unwrap().id, 2);
+
+        assert_eq!(tree.as_ref().lower_bound(&1).count(), 20);
+        assert_eq!(tree.as_ref().lower_bound(&1).next().unwrap().id, 1);
+        assert_eq!(tree.as_ref().upper_bound(&1).count(), 10);
+        assert_eq!(tree.as_ref().upper_bound(&1).next().unwrap().id, 2);
+        assert!(tree.as_ref().upper_bound(&2).next().is_none());
+        assert_eq!(tree.as_ref().lower_bound(&-5).count(), 30);
+
+        for mut y in tree.as_mut().equal_range_mut(&0) {
+            y.id += 100;
+        }
+        assert_eq!(tree.as_ref().front().unwrap().id, 100);
+
+        while Y::from(tree.as_mut().pop_front()).is_some() {
+            check::<Y, YLink>(tree.link.top(), None);
+        }
+    }
+
+    #[test]
+    fn test_multi_remove() {
+        let mut tree = Box::pin(AVLMultiTree::new(YLink));
+        let items: Vec<_> = (0..10).map(|id| Y::new(id / 4, id)).collect();
+        for item in &items {
+            tree.as_mut().insert(*item);
+        }
+        // [0,0,0,0,1,1,1,1,2,2]
+        let item = Y::from(tree.as_mut().remove(unsafe { items[5].as_ref() })).unwrap();
+        assert_eq!(item.id, 5);
+        check::<Y, YLink>(tree.link.top(), None);
+        assert!(tree.as_ref().equal_range(&1).map(|y| y.id).eq([4, 6, 7]));
+
+        // an equal key that is not an element of the tree
+        let stranger = Y {
+            key: 1,
+            id: 0,
+            link: Link::new(),
+        };
+        assert!(tree.as_mut().remove(&stranger).is_none());
+        assert_eq!(tree.as_ref().len(), 9);
+
+        for id in [0, 9, 6, 2] {
+            let item = Y::from(tree.as_mut().remove(unsafe { items[id].as_ref() })).unwrap();
+            assert_eq!(item.id, id as i32);
+            check::<Y, YLink>(tree.link.top(), None);
+        }
+        assert!(tree.as_ref().iter().map(|y| y.id).eq([1, 3, 4, 7, 8]));
+        while Y::from(tree.as_mut().pop_back()).is_some() {}
+    }
+
+    struct W {
+        data: i32,
+        // every live element holds a clone, so the count tracks the releases
+        _alive: Rc<()>,
+        link: Link<Self>,
+    }
+
+    intrusive_adapter! {
+        WLink = W { link: Link<W>, key = data: i32 } with NumerateSize, Box<W>;
+    }
+
+    #[test]
+    fn test_set_algebra() {
+        fn data<'a>(it: impl Iterator<Item = Pin<&'a X>>) -> Vec<i32> {
+            it.map(|x| x.data).collect()
+        }
+
+        let mut a = Box::pin(AVLTree::new(XSizeLink));
+        let mut b = Box::pin(AVLTree::new(XSizeLink));
+        for i in 0..100 {
+            a.as_mut().insert(X::new(i));
+        }
+        for i in (0..150).step_by(3) {
+            b.as_mut().insert(X::new(i));
+        }
+        let (a_, b_) = (a.as_ref(), b.as_ref());
+        assert_eq!(a_.union(b_).count(), 116);
+        assert_eq!(
+            data(a_.intersection(b_)),
+            (0..100).step_by(3).collect::<Vec<_>>()
+        );
+        assert_eq!(a_.difference(b_).count(), 66);
+        assert_eq!(
+            data(b_.difference(a_)),
+            (102..150).step_by(3).collect::<Vec<_>>()
+        );
+        assert_eq!(a_.symmetric_difference(b_).count(), 66 + 16);
+
+        let mut c = Box::pin(AVLTree::new(XSizeLink));
+        a.as_mut().extract_difference(b.as_ref(), c.as_mut());
+        check::<X, XSizeLink>(a.link.top(), None);
+        check::<X, XSizeLink>(c.link.top(), None);
+        assert_eq!(
+            data(a.as_ref().iter()),
+            (0..100).step_by(3).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            data(c.as_ref().iter()),
+            (0..100).filter(|i| i % 3 != 0).collect::<Vec<_>>()
+        );
+        assert_eq!(c.as_ref().len(), 66);
+
+        a.as_mut().union_with(b.as_mut());
+        check::<X, XSizeLink>(a.link.top(), None);
+        check::<X, XSizeLink>(b.link.top(), None);
+        assert_eq!(
+            data(a.as_ref().iter()),
+            (0..150).step_by(3).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            data(b.as_ref().iter()),
+            (0..100).step_by(3).collect::<Vec<_>>()
+        );
+        assert_eq!(a.as_ref().len(), 50);
+        assert_eq!(b.as_ref().len(), 34);
+
+        let mut d = Box::pin(AVLTree::new(XSizeLink));
+        a.as_mut().extract_intersection(b.as_ref(), d.as_mut());
+        check::<X, XSizeLink>(a.link.top(), None);
+        check::<X, XSizeLink>(d.link.top(), None);
+        assert_eq!(
+            data(d.as_ref().iter().rev()),
+            (0..100).step_by(3).rev().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            data(a.as_ref().iter()),
+            (102..150).step_by(3).collect::<Vec<_>>()
+        );
+
+        for mut tree in [a, b, c, d] {
+            while X::from(tree.as_mut().pop_front()).is_some() {}
+        }
+    }
+
+    #[test]
+    fn test_owning() {
+        let alive = Rc::new(());
+        let new = |data| {
+            Box::new(W {
+                data,
+                _alive: alive.clone(),
+                link: Link::new(),
+            })
+        };
+        let mut tree = Box::pin(AVLTree::new(WLink));
+        for data in [4, 2, 6, 1, 3, 5, 7] {
+            assert!(tree.as_mut().insert(new(data)).is_none());
+        }
+        assert_eq!(Rc::strong_count(&alive), 8);
+        let dup = tree.as_mut().insert(new(4)).unwrap();
+        assert!(!dup.link.is_linked());
+        drop(dup);
+        let item = tree.as_mut().pop_front().unwrap();
+        assert_eq!(item.data, 1);
+        assert!(!item.link.is_linked());
+        drop(item);
+        assert_eq!(Rc::strong_count(&alive), 7);
+        tree.as_mut().clear();
+        assert!(tree.as_ref().is_empty());
+        assert_eq!(Rc::strong_count(&alive), 1);
+
+        for data in [2, 1, 3] {
+            tree.as_mut().insert(new(data));
+        }
+        let item = tree.as_mut().remove(&2).unwrap();
+        assert_eq!(item.data, 2);
+        assert_eq!(tree.as_ref().len(), 2);
+        drop(item);
+        drop(tree);
+        assert_eq!(Rc::strong_count(&alive), 1);
+    }
+
+    intrusive_adapter! {
+        XRcLink = X { link: Link<X>, key = data: i32 } with NumerateSize, Rc<X>;
+    }
+
+    #[test]
+    fn test_owning_rc() {
+        let items: Vec<_> = [4, 2, 6, 1, 3, 5, 7]
+            .into_iter()
+            .map(|data| {
+                Rc::new(X {
+                    data,
+                    link: Link::new(),
+                })
+            })
+            .collect();
+        let mut tree = Box::pin(AVLTree::new(XRcLink));
+        for item in &items {
+            assert!(tree.as_mut().insert(item.clone()).is_none());
+        }
+        assert!(items.iter().all(|x| Rc::strong_count(x) == 2));
+        assert!(items.iter().all(|x| x.link.is_linked()));
+        check::<X, XRcLink>(tree.link.top(), None);
+        let dup = Rc::new(X {
+            data: 4,
+            link: Link::new(),
+        });
+        let item = tree.as_mut().insert(dup.clone()).unwrap();
+        assert!(Rc::ptr_eq(&item, &dup));
+        drop(item);
+        assert_eq!(Rc::strong_count(&dup), 1);
+        let item = tree.as_mut().remove(&1).unwrap();
+        assert!(Rc::ptr_eq(&item, &items[3]));
+        drop(item);
+        assert_eq!(Rc::strong_count(&items[3]), 1);
+        assert!(!items[3].link.is_linked());
+        tree.as_mut().clear();
+        assert!(tree.as_ref().is_empty());
+        assert!(items.iter().all(|x| Rc::strong_count(x) == 1));
+        assert!(items.iter().all(|x| !x.link.is_linked()));
+
+        for item in &items {
+            tree.as_mut().insert(item.clone());
+        }
+        drop(tree);
+        assert!(items.iter().all(|x| Rc::strong_count(x) == 1));
+        assert!(items.iter().all(|x| !x.link.is_linked()));
+    }
+
+    struct Z {
+        name: String,
+        link: Link<Self>,
+    }
+
+    impl Z {
+        fn new(name: &str) -> Box<Self> {
+            Box::new(Z {
+                name: name.to_string(),
+                link: Link::new(),
+            })
+        }
+    }
+
+    intrusive_adapter! {
+        ZLink = Z { link: Link<Z>, key = name: String } with NumerateSize, Box<Z>;
+    }
+
+    #[test]
+    fn test_key_adapter() {
+        let mut tree = Box::pin(AVLTree::new(ZLink));
+        for name in ["m", "c", "x", "a", "e"] {
+            assert!(tree.as_mut().insert(Z::new(name)).is_none());
+        }
+        assert_eq!(tree.as_mut().insert(Z::new("e")).unwrap().name, "e");
+
+        assert_eq!(tree.as_ref().get("c").unwrap().name, "c");
+        assert!(tree.as_ref().get("b").is_none());
+        assert!(AVLTree::get_mut(tree.as_mut(), "x").is_some());
+        assert_eq!(tree.as_mut().remove("m").unwrap().name, "m");
+        assert!(tree.as_mut().remove("m").is_none());
+
+        let mut multi = Box::pin(AVLMultiTree::new(ZLink));
+        for name in ["b", "a", "b", "c", "b"] {
+            multi.as_mut().insert(Z::new(name));
+        }
+        assert_eq!(multi.as_ref().count("b"), 3);
+        assert_eq!(multi.as_ref().get("c").unwrap().name, "c");
+        assert_eq!(multi.as_ref().lower_bound("b").count(), 4);
+        assert_eq!(multi.as_ref().upper_bound("b").count(), 1);
+    }
+}
@@ -1,34 +1,46 @@
-use crate::adapter::{LinkAdapter, Size};
-use crate::ptr::{NonNullPtr, Pointer};
+use crate::adapter::{Compare, KeyAdapter, LinkAdapter, NaturalOrder, Size};
+use crate::ptr::{NonNullPtr, OwningPointer, Pointer, UniquePointer};
+use std::borrow::Borrow;
+use std::cell::Cell;
 use std::cmp;
 use std::fmt;
+use std::iter::{self, Peekable};
 use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
 use std::pin::Pin;
 use std::ptr::{self, NonNull};
 
+#[repr(C)]
 pub struct Link<T, P = NonNull<T>> {
-    top_ptr: Option<Pin<NonNullPtr<T, P>>>,
-    left_ptr: Option<Pin<NonNullPtr<T, P>>>,
-    right_ptr: Option<Pin<NonNullPtr<T, P>>>,
+    top_ptr: Cell<Option<Pin<NonNullPtr<T, P>>>>,
+    left_ptr: Cell<Option<Pin<NonNullPtr<T, P>>>>,
+    right_ptr: Cell<Option<Pin<NonNullPtr<T, P>>>>,
 }
 
 impl<T, P> Link<T, P> {
     pub const fn new() -> Self {
         Self {
-            top_ptr: None,
-            left_ptr: None,
-            right_ptr: None,
+            top_ptr: Cell::new(None),
+            left_ptr: Cell::new(None),
+            right_ptr: Cell::new(None),
         }
     }
 
+    /// Whether the element has a parent or a child in a tree, which the root
+    /// of a tree with one element does not.
     pub const fn is_linked(&self) -> bool {
-        self.top_ptr.is_some() || self.left_ptr.is_some() || self.right_ptr.is_some()
+        unsafe {
+            (*self.top_ptr.as_ptr()).is_some()
+                || (*self.left_ptr.as_ptr()).is_some()
+                || (*self.right_ptr.as_ptr()).is_some()
+        }
     }
 
-    fn unlink(&mut self) {
-        self.top_ptr = None;
-        self.left_ptr = None;
-        self.right_ptr = None;
+    pub(crate) fn unlink(&self) {
+        self.top_ptr.set(None);
+        self.left_ptr.set(None);
+        self.right_ptr.set(None);
     }
 }
 
@@ -37,507 +49,1931 @@ where
     T: Unpin,
     P: Pointer<T>,
 {
-    fn first_left<A>(&self) -> Option<&Pin<NonNullPtr<T, P>>>
+    /// Successor of the node owning `self`; from the header this is the minimum.
+    fn next_link<A>(&self) -> Option<NonNull<T>>
     where
         A: LinkAdapter<T, Link = Link<T, P>>,
     {
-        if let Some(left) = &self.left_ptr {
-            let left_link = A::link_ref(left.as_ref().get_ref());
-            if let Some(top) = &left_link.top_ptr {
-                let top_link = A::link_ref(top.as_ref().get_ref());
-                if ptr::addr_eq(self, top_link) {
-                    None
-                } else {
-                    Some(left)
-                }
-            } else if let Some(top) = &self.top_ptr {
-                let top_link = A::link_ref(top.as_ref().get_ref());
-                if ptr::addr_eq(self, top_link) {
-                    None
-                } else {
-                    Some(left)
-                }
-            } else {
-                None
+        if let Some(left) = self.left() {
+            // the left end seen from the header is no child of it
+            let top = Self::of::<A>(left).top();
+            if top.is_none_or(|top| !ptr::eq(Self::of::<A>(top), self)) {
+                return Some(left);
             }
-        } else {
-            None
         }
+        if let Some(right) = self.right() {
+            return Some(Self::first_of::<A>(right));
+        }
+        let mut link = self;
+        while let Some(top) = link.top() {
+            let top_link = Self::of::<A>(top);
+            if top_link
+                .left()
+                .is_some_and(|left| ptr::eq(Self::of::<A>(left), link))
+            {
+                return Some(top);
+            }
+            link = top_link;
+        }
+        None
     }
 
-    fn first_left_mut<A>(&mut self) -> Option<&mut Pin<NonNullPtr<T, P>>>
-        where
-            A: LinkAdapter<T, Link = Link<T, P>>,
+    /// Predecessor of the node owning `self`; from the header this is the maximum.
+    fn prev_link<A>(&self) -> Option<NonNull<T>>
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
     {
-        let self_ = self as *const Self;
-        if let Some(left) = &mut self.left_ptr {
-            let left_link = A::link_mut(left.as_mut().get_mut());
-            if let Some(top) = &mut left_link.top_ptr {
-                let top_link = A::link_mut(top.as_mut().get_mut());
-                if ptr::addr_eq(self_, top_link) {
-                    None
-                } else {
-                    Some(left)
-                }
-            } else if let Some(top) = &mut self.top_ptr {
-                let top_link = A::link_mut(top.as_mut().get_mut());
-                if ptr::addr_eq(self_, top_link) {
-                    None
-                } else {
-                    Some(left)
-                }
-            } else {
-                None
+        if let Some(right) = self.right() {
+            // the right end seen from the header is no child of it
+            let top = Self::of::<A>(right).top();
+            if top.is_none_or(|top| !ptr::eq(Self::of::<A>(top), self)) {
+                return Some(right);
             }
-        } else {
-            None
         }
+        if let Some(left) = self.left() {
+            return Some(Self::last_of::<A>(left));
+        }
+        let mut link = self;
+        while let Some(top) = link.top() {
+            let top_link = Self::of::<A>(top);
+            if top_link
+                .right()
+                .is_some_and(|right| ptr::eq(Self::of::<A>(right), link))
+            {
+                return Some(top);
+            }
+            link = top_link;
+        }
+        None
     }
 
-    fn is_left<A>(&self, link: &Self) -> bool
+    pub(crate) fn of<'a, A>(node: NonNull<T>) -> &'a Self
     where
         A: LinkAdapter<T, Link = Link<T, P>>,
     {
-        if let Some(left) = &self.left_ptr {
-            ptr::addr_eq(link, A::link_ref(left))
-        } else {
-            false
-        }
+        A::link_ref(unsafe { &*node.as_ptr() })
     }
-}
 
-impl<T, P> Default for Link<T, P> {
-    fn default() -> Self {
-        Self::new()
+    /// Panics if `node` is already in a tree. A lone root has no links and
+    /// looks unlinked, so the root below this header is checked too; the lone
+    /// root of another tree still goes unnoticed.
+    pub(crate) fn assert_unlinked<A>(&self, node: NonNull<T>)
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        assert!(
+            !Self::of::<A>(node).is_linked() && self.top() != Some(node),
+            "the element is already linked"
+        );
     }
-}
 
-impl<T, P> Unpin for Link<T, P> where T: Unpin {}
+    pub(crate) fn top(&self) -> Option<NonNull<T>> {
+        NonNullPtr::to_raw(&self.top_ptr)
+    }
 
-impl<T, P> cmp::PartialEq for Link<T, P> {
-    fn eq(&self, _: &Self) -> bool {
-        true
+    pub(crate) fn left(&self) -> Option<NonNull<T>> {
+        NonNullPtr::to_raw(&self.left_ptr)
     }
-}
 
-impl<T, P> cmp::Eq for Link<T, P> {}
+    pub(crate) fn right(&self) -> Option<NonNull<T>> {
+        NonNullPtr::to_raw(&self.right_ptr)
+    }
 
-impl<T, P> cmp::PartialOrd for Link<T, P> {
-    fn partial_cmp(&self, _: &Self) -> Option<cmp::Ordering> {
-        Some(cmp::Ordering::Equal)
+    /// Unlinks every node below the header without rebalancing, handing each to `f`.
+    pub(crate) fn drain<A>(&mut self, mut f: impl FnMut(NonNull<T>))
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        let mut node = self.top();
+        self.unlink();
+        while let Some(item) = node {
+            let item_link = Self::of::<A>(item);
+            if let Some(left) = item_link.left() {
+                item_link.left_ptr.set(None);
+                node = Some(left);
+            } else if let Some(right) = item_link.right() {
+                item_link.right_ptr.set(None);
+                node = Some(right);
+            } else {
+                node = item_link.top();
+                item_link.unlink();
+                f(item);
+            }
+        }
     }
-}
 
-impl<T, P> cmp::Ord for Link<T, P> {
-    fn cmp(&self, _: &Self) -> cmp::Ordering {
-        cmp::Ordering::Equal
+    pub(crate) fn first_of<A>(mut node: NonNull<T>) -> NonNull<T>
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        while let Some(left) = Self::of::<A>(node).left() {
+            node = left;
+        }
+        node
     }
-}
 
-impl<T, P> fmt::Debug for Link<T, P>
-where
-    P: fmt::Debug,
-{
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{{ top: ")?;
-        if let Some(top) = &self.top_ptr {
-            write!(f, "{:?}", top)?;
-        } else {
-            write!(f, "0x0")?;
+    pub(crate) fn last_of<A>(mut node: NonNull<T>) -> NonNull<T>
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        while let Some(right) = Self::of::<A>(node).right() {
+            node = right;
         }
-        write!(f, ", left: ")?;
-        if let Some(left) = &self.left_ptr {
-            write!(f, "{:?}", left)?;
-        } else {
-            write!(f, "0x0")?;
+        node
+    }
+
+    pub(crate) fn next_of<A>(mut node: NonNull<T>) -> Option<NonNull<T>>
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        if let Some(right) = Self::of::<A>(node).right() {
+            return Some(Self::first_of::<A>(right));
         }
-        write!(f, ", right: ")?;
-        if let Some(right) = &self.right_ptr {
-            write!(f, "{:?}", right)?;
-        } else {
-            write!(f, "0x0")?;
+        while let Some(top) = Self::of::<A>(node).top() {
+            if Self::of::<A>(top).left() == Some(node) {
+                return Some(top);
+            }
+            node = top;
         }
-        write!(f, " }}")
+        None
     }
-}
-
-pub struct Iter<'a, T, A, P> {
-    link: *const Link<T, P>,
-    _marker: PhantomData<&'a A>,
-}
-
-impl<'a, T, A, P> Iterator for Iter<'a, T, A, P>
-where
-    T: Unpin + 'a,
-    P: Pointer<T> + 'a,
-    A: LinkAdapter<T, Link = Link<T, P>>,
-{
-    type Item = Pin<&'a T>;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let link = unsafe { &*self.link };
-        if let Some(node) = link.first_left::<A>() {
-            self.link = A::link_ref(node.as_ref().get_ref());
-            Some(node.as_ref())
-        } else if let Some(node) = &link.right_ptr {
-            let mut node = node as *const Pin<NonNullPtr<T, P>>;
-            loop {
-                let node_link = A::link_ref(unsafe { &*node }.as_ref().get_ref());
-                if let Some(left) = &node_link.left_ptr {
-                    node = left;
-                } else {
-                    self.link = node_link;
-                    return Some(unsafe { &*node }.as_ref());
-                }
-            }
-        } else if let Some(node) = &link.top_ptr {
-            let mut node = node as *const Pin<NonNullPtr<T, P>>;
-            let mut link = link as *const Link<T, P>;
-            loop {
-                let node_link = A::link_ref(unsafe { &*node }.as_ref().get_ref());
-                if node_link.is_left::<A>(unsafe { &*link }) {
-                    self.link = node_link;
-                    return Some(unsafe { &*node }.as_ref());
-                } else if let Some(top) = &node_link.top_ptr {
-                    link = node_link;
-                    node = top;
-                } else {
-                    return None;
-                }
+    pub(crate) fn prev_of<A>(mut node: NonNull<T>) -> Option<NonNull<T>>
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        if let Some(left) = Self::of::<A>(node).left() {
+            return Some(Self::last_of::<A>(left));
+        }
+        while let Some(top) = Self::of::<A>(node).top() {
+            if Self::of::<A>(top).right() == Some(node) {
+                return Some(top);
             }
-        } else {
-            None
+            node = top;
         }
+        None
     }
-}
 
-impl<'a, T, A, P> DoubleEndedIterator for Iter<'a, T, A, P>
-where
-    T: Unpin + 'a,
-    P: Pointer<T> + 'a,
-    A: LinkAdapter<T, Link = Link<T, P>>,
-{
-    fn next_back(&mut self) -> Option<Self::Item> {
-        todo!()
+    pub(crate) fn replace_child<A>(
+        &mut self,
+        top: Option<NonNull<T>>,
+        old: NonNull<T>,
+        new: Option<NonNull<T>>,
+    ) where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        if let Some(top) = top {
+            let top_link = Self::of::<A>(top);
+            if top_link.left() == Some(old) {
+                NonNullPtr::assign_raw(&top_link.left_ptr, new);
+            } else {
+                NonNullPtr::assign_raw(&top_link.right_ptr, new);
+            }
+        } else {
+            NonNullPtr::assign_raw(&self.top_ptr, new);
+        }
     }
-}
-
-pub struct IterMut<'a, T, A, P> {
-    link: *mut Link<T, P>,
-    _marker: PhantomData<&'a A>,
-}
 
-impl<'a, T, A, P> Iterator for IterMut<'a, T, A, P>
-where
-    T: Unpin + 'a,
-    P: Pointer<T> + 'a,
-    A: LinkAdapter<T, Link = Link<T, P>>,
-{
-    type Item = Pin<&'a mut T>;
+    /// Unlinks `node`, splicing its successor into its place if it has two
+    /// children, and reports what moved for the trees that rebalance after.
+    pub(crate) fn unlink_node<A>(&mut self, node: NonNull<T>) -> Vacancy<T>
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        if self.left() == Some(node) {
+            NonNullPtr::assign_raw(&self.left_ptr, Self::next_of::<A>(node));
+        }
+        if self.right() == Some(node) {
+            NonNullPtr::assign_raw(&self.right_ptr, Self::prev_of::<A>(node));
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let link = self.link;
-        if let Some(node) = unsafe { &mut *link }.first_left_mut::<A>() {
-            self.link = A::link_mut(node.as_mut().get_mut());
-            Some(node.as_mut())
-        } else if let Some(node) = &mut unsafe { &mut *link }.right_ptr {
-            let mut node = node as *mut Pin<NonNullPtr<T, P>>;
-            loop {
-                let node_link = A::link_mut(unsafe { &mut *node }.as_mut().get_mut());
-                if let Some(left) = &mut node_link.left_ptr {
-                    node = left;
+        let node_link = Self::of::<A>(node);
+        let top = node_link.top();
+        let vacancy = match (node_link.left(), node_link.right()) {
+            (Some(left), Some(right)) => {
+                // splice the successor into the place of node
+                let next = Self::first_of::<A>(right);
+                let next_link = Self::of::<A>(next);
+                let next_right = next_link.right();
+                let next_top = if next == right {
+                    next
                 } else {
-                    self.link = node_link;
-                    return Some(unsafe { &mut *node }.as_mut());
+                    let next_top = next_link.top().unwrap();
+                    NonNullPtr::assign_raw(&Self::of::<A>(next_top).left_ptr, next_right);
+                    if let Some(next_right) = next_right {
+                        NonNullPtr::assign(&Self::of::<A>(next_right).top_ptr, next_top);
+                    }
+                    NonNullPtr::assign(&next_link.right_ptr, right);
+                    NonNullPtr::assign(&Self::of::<A>(right).top_ptr, next);
+                    next_top
+                };
+                NonNullPtr::assign(&next_link.left_ptr, left);
+                NonNullPtr::assign(&Self::of::<A>(left).top_ptr, next);
+                NonNullPtr::assign_raw(&next_link.top_ptr, top);
+                self.replace_child::<A>(top, node, Some(next));
+                Vacancy {
+                    next: Some(next),
+                    top: Some(next_top),
+                    child: next_right,
                 }
             }
-        } else if let Some(node) = &mut unsafe { &mut *link }.top_ptr {
-            let mut node = node as *mut Pin<NonNullPtr<T, P>>;
-            let mut link = link as *mut Link<T, P>;
-            loop {
-                let node_link = A::link_mut(unsafe { &mut *node }.as_mut().get_mut()) as *mut Link<T, P>;
-                if unsafe { &mut *node_link }.is_left::<A>(unsafe { &mut *link }) {
-                    self.link = node_link;
-                    return Some(unsafe { &mut *node }.as_mut());
-                } else if let Some(top) = &mut unsafe { &mut *node_link }.top_ptr {
-                    link = node_link;
-                    node = top;
-                } else {
-                    return None;
+            (child, None) | (None, child) => {
+                if let Some(child) = child {
+                    NonNullPtr::assign_raw(&Self::of::<A>(child).top_ptr, top);
                 }
+                self.replace_child::<A>(top, node, child);
+                Vacancy {
+                    next: None,
+                    top,
+                    child,
+                }
+            }
+        };
+        node_link.unlink();
+        vacancy
+    }
+
+    /// Links `node` as a leaf at `slot`, found by a search that fell off the tree.
+    pub(crate) fn link_leaf<A>(&mut self, slot: &Slot<T>, node: NonNull<T>)
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        let node_link = Self::of::<A>(node);
+        let Some(top) = slot.top else {
+            NonNullPtr::assign(&self.top_ptr, node);
+            NonNullPtr::assign(&self.left_ptr, node);
+            NonNullPtr::assign(&self.right_ptr, node);
+            return;
+        };
+        NonNullPtr::assign(&node_link.top_ptr, top);
+        let top_link = Self::of::<A>(top);
+        if slot.is_left {
+            NonNullPtr::assign(&top_link.left_ptr, node);
+            if slot.is_left_end {
+                NonNullPtr::assign(&self.left_ptr, node);
             }
         } else {
-            None
+            NonNullPtr::assign(&top_link.right_ptr, node);
+            if slot.is_right_end {
+                NonNullPtr::assign(&self.right_ptr, node);
+            }
         }
     }
-}
 
-impl<'a, T, A, P> DoubleEndedIterator for IterMut<'a, T, A, P>
-where
-    T: Unpin + 'a,
-    P: Pointer<T> + 'a,
-    A: LinkAdapter<T, Link = Link<T, P>>,
-{
-    fn next_back(&mut self) -> Option<Self::Item> {
-        todo!()
+    /// Puts `new` into the place of the linked `old`, which is left unlinked.
+    fn replace_node<A>(&mut self, old: NonNull<T>, new: NonNull<T>)
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        let old_link = Self::of::<A>(old);
+        let (top, left, right) = (old_link.top(), old_link.left(), old_link.right());
+        old_link.unlink();
+
+        let new_link = Self::of::<A>(new);
+        NonNullPtr::assign_raw(&new_link.top_ptr, top);
+        NonNullPtr::assign_raw(&new_link.left_ptr, left);
+        NonNullPtr::assign_raw(&new_link.right_ptr, right);
+        for child in [left, right].into_iter().flatten() {
+            NonNullPtr::assign(&Self::of::<A>(child).top_ptr, new);
+        }
+        self.replace_child::<A>(top, old, Some(new));
+        if self.left() == Some(old) {
+            NonNullPtr::assign(&self.left_ptr, new);
+        }
+        if self.right() == Some(old) {
+            NonNullPtr::assign(&self.right_ptr, new);
+        }
     }
 }
 
-#[derive(Debug)]
-pub struct BinTree<T, A, P = NonNull<T>>
+impl<T, P> Link<T, P>
 where
-    A: LinkAdapter<T>,
+    T: Unpin,
+    P: Pointer<T>,
 {
-    size: A::Size,
-    link: Link<T, P>,
-}
+    /// Links `node` as the left or right child of `top`, or as the root below the header.
+    pub(crate) fn hang<A>(&mut self, top: Option<NonNull<T>>, node: NonNull<T>, is_left: bool)
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        NonNullPtr::assign_raw(&Self::of::<A>(node).top_ptr, top);
+        match top {
+            Some(top) if is_left => NonNullPtr::assign(&Self::of::<A>(top).left_ptr, node),
+            Some(top) => NonNullPtr::assign(&Self::of::<A>(top).right_ptr, node),
+            None => NonNullPtr::assign(&self.top_ptr, node),
+        }
+    }
 
-impl<T, A, P> BinTree<T, A, P>
-where
-    A: LinkAdapter<T>,
-{
-    pub fn new(_: A) -> Self {
-        Self {
-            size: Default::default(),
-            link: Link::new(),
+    /// Lifts the right child of `node` into its place.
+    pub(crate) fn rotate_left<A>(&mut self, node: NonNull<T>)
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        let node_link = Self::of::<A>(node);
+        let top = node_link.top();
+        let right = node_link.right().unwrap();
+        let right_link = Self::of::<A>(right);
+        let inner = right_link.left();
+        NonNullPtr::assign_raw(&node_link.right_ptr, inner);
+        if let Some(inner) = inner {
+            NonNullPtr::assign(&Self::of::<A>(inner).top_ptr, node);
         }
+        NonNullPtr::assign(&right_link.left_ptr, node);
+        NonNullPtr::assign(&node_link.top_ptr, right);
+        NonNullPtr::assign_raw(&right_link.top_ptr, top);
+        self.replace_child::<A>(top, node, Some(right));
     }
-}
 
-impl<T, A, P> BinTree<T, A, P>
-where
-    T: Unpin + cmp::Ord,
-    P: Pointer<T>,
-    A: LinkAdapter<T, Link = Link<T, P>>,
-{
-    pub fn get(self: Pin<&Self>, key: &T) -> Option<Pin<&T>> {
-        let self_ = Pin::into_inner(self);
-        let mut node_ptr = &self_.link.top_ptr as *const Option<Pin<NonNullPtr<T, P>>>;
-        loop {
-            if let Some(node) = unsafe { &*node_ptr } {
-                match node.as_ref().get_ref().cmp(key) {
-                    cmp::Ordering::Equal => return Some(Pin::new(node)),
-                    cmp::Ordering::Less => {
-                        let link: &Link<T, P> = A::link_ref(node);
-                        node_ptr = &link.left_ptr
-                    }
-                    cmp::Ordering::Greater => {
-                        let link: &Link<T, P> = A::link_ref(node);
-                        node_ptr = &link.right_ptr
-                    }
-                }
-            } else {
-                return None;
-            }
+    /// Lifts the left child of `node` into its place.
+    pub(crate) fn rotate_right<A>(&mut self, node: NonNull<T>)
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        let node_link = Self::of::<A>(node);
+        let top = node_link.top();
+        let left = node_link.left().unwrap();
+        let left_link = Self::of::<A>(left);
+        let inner = left_link.right();
+        NonNullPtr::assign_raw(&node_link.left_ptr, inner);
+        if let Some(inner) = inner {
+            NonNullPtr::assign(&Self::of::<A>(inner).top_ptr, node);
         }
+        NonNullPtr::assign(&left_link.right_ptr, node);
+        NonNullPtr::assign(&node_link.top_ptr, left);
+        NonNullPtr::assign_raw(&left_link.top_ptr, top);
+        self.replace_child::<A>(top, node, Some(left));
     }
 
-    pub fn get_mut(self: Pin<&mut Self>, key: &T) -> Option<Pin<&mut T>> {
-        let self_ = Pin::into_inner(self);
-        let mut node_ptr: *mut Option<Pin<NonNullPtr<T, P>>> = &mut self_.link.top_ptr;
-        loop {
-            if let Some(node) = unsafe { &mut *node_ptr } {
-                match node.as_ref().get_ref().cmp(key) {
-                    cmp::Ordering::Equal => return Some(Pin::new(node)),
-                    cmp::Ordering::Less => {
-                        let link: &mut Link<T, P> = A::link_mut(node);
-                        node_ptr = &mut link.left_ptr
-                    }
-                    cmp::Ordering::Greater => {
-                        let link: &mut Link<T, P> = A::link_mut(node);
-                        node_ptr = &mut link.right_ptr
-                    }
-                }
+    /// Rotates the nodes below the header into a balanced shape (Day-Stout-Warren).
+    pub(crate) fn rebalance<A>(&mut self)
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        // rotate every left child up until the tree is a right-leaning vine
+        let mut len: usize = 0;
+        let mut node = self.top();
+        while let Some(item) = node {
+            if Self::of::<A>(item).left().is_some() {
+                self.rotate_right::<A>(item);
+                node = Self::of::<A>(item).top();
             } else {
-                return None;
+                len += 1;
+                node = Self::of::<A>(item).right();
             }
         }
+        if len < 3 {
+            return;
+        }
+
+        // fold the vine by rotating every other node left, leaving the
+        // nodes beyond a perfect tree at the bottom level first
+        let perfect = (1 << (len + 1).ilog2()) - 1;
+        self.compress::<A>(len - perfect);
+        let mut count = perfect;
+        while count > 1 {
+            count /= 2;
+            self.compress::<A>(count);
+        }
     }
 
-    pub fn insert(self: Pin<&mut Self>, mut item: NonNull<T>) -> Option<NonNull<T>> {
-        let item_link = A::link_mut(unsafe { item.as_mut() });
-        debug_assert_eq!(item_link.is_linked(), false);
+    fn compress<A>(&mut self, count: usize)
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        let mut node = self.top();
+        for _ in 0..count {
+            let item = node.unwrap();
+            let right = Self::of::<A>(item).right().unwrap();
+            self.rotate_left::<A>(item);
+            node = Self::of::<A>(right).right();
+        }
+    }
 
-        let self_ = Pin::into_inner(self);
-        self_.size.incr();
-        let mut node = if let Some(node) = &mut self_.link.top_ptr {
-            node as *mut Pin<NonNullPtr<T, P>>
-        } else {
-            NonNullPtr::assign(&mut self_.link.top_ptr, item);
-            NonNullPtr::assign(&mut self_.link.left_ptr, item);
-            NonNullPtr::assign(&mut self_.link.right_ptr, item);
-            return None;
+    /// Points the header at the first and last nodes below it.
+    pub(crate) fn reset_ends<A>(&mut self)
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        let top = self.top();
+        self.set_ends(top.map(Self::first_of::<A>), top.map(Self::last_of::<A>));
+    }
+
+    /// Points the header at `first` and `last`, already known to be the ends below it.
+    pub(crate) fn set_ends(&mut self, first: Option<NonNull<T>>, last: Option<NonNull<T>>) {
+        NonNullPtr::assign_raw(&self.left_ptr, first);
+        NonNullPtr::assign_raw(&self.right_ptr, last);
+    }
+
+    pub(crate) fn search<A, Q, C>(&self, key: &Q, compare: &C) -> Result<NonNull<T>, Slot<T>>
+    where
+        A: KeyAdapter<T, Link = Link<T, P>>,
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let mut slot = Slot {
+            top: None,
+            is_left: true,
+            is_left_end: true,
+            is_right_end: true,
         };
-        let mut is_left_end = true;
-        let mut is_right_end = true;
-        loop {
-            let cmp = unsafe { item.as_ref().cmp((&*node).as_ref().get_ref()) };
-            match cmp {
+        let mut node = self.top();
+        while let Some(item) = node {
+            let item_key = A::get_key(unsafe { item.as_ref() }).borrow();
+            slot.top = Some(item);
+            node = match compare.compare(key, item_key) {
+                cmp::Ordering::Equal => return Ok(item),
                 cmp::Ordering::Less => {
-                    // item < node
-                    let link = A::link_mut(unsafe { &mut *node }.as_mut().get_mut());
-                    if let Some(left) = &mut link.left_ptr {
-                        node = left;
-                    } else {
-                        NonNullPtr::assign_pin(&mut item_link.top_ptr, unsafe { &mut *node });
-                        NonNullPtr::assign(&mut link.left_ptr, item);
-                        if is_left_end {
-                            NonNullPtr::assign(&mut self_.link.left_ptr, item);
-                        }
-                        return None;
-                    }
-                    is_right_end = false;
+                    slot.is_left = true;
+                    slot.is_right_end = false;
+                    Link::of::<A>(item).left()
                 }
                 cmp::Ordering::Greater => {
-                    // item > node
-                    let link = A::link_mut(unsafe { &mut *node }.as_mut().get_mut());
-                    if let Some(right) = &mut link.right_ptr {
-                        node = right;
-                    } else {
-                        NonNullPtr::assign_pin(&mut item_link.top_ptr, unsafe { &mut *node });
-                        NonNullPtr::assign(&mut link.right_ptr, item);
-                        if is_right_end {
-                            NonNullPtr::assign(&mut self_.link.right_ptr, item);
-                        }
-                        return None;
-                    }
-                    is_left_end = false;
+                    slot.is_left = false;
+                    slot.is_left_end = false;
+                    Link::of::<A>(item).right()
                 }
-                _ => return Some(item),
-            }
+            };
         }
+        Err(slot)
     }
 
-    pub fn pop_front(self: Pin<&mut Self>) -> Option<NonNull<T>> {
-        let self_ = Pin::into_inner(self);
-        if let Some(node) = &mut self_.link.left_ptr {
-            let mut node = NonNull::from(node.as_mut().get_mut());
-            let node_link = A::link_mut(unsafe { node.as_mut() });
-            if let Some(right) = &node_link.right_ptr {
-                todo!()
-            } else if let Some(top) = &mut node_link.top_ptr {
-                NonNullPtr::assign_pin(&mut self_.link.left_ptr, top);
-                let top_link = A::link_mut(unsafe { top.as_mut().get_mut() });
-                top_link.left_ptr = None;
+    pub(crate) fn lower_node<A, Q, C>(&self, bound: Bound<&Q>, compare: &C) -> Option<NonNull<T>>
+    where
+        A: KeyAdapter<T, Link = Link<T, P>>,
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let mut node = self.top();
+        let mut found = None;
+        while let Some(item) = node {
+            let item_key = A::get_key(unsafe { item.as_ref() }).borrow();
+            let is_above = match bound {
+                Bound::Included(key) => compare.compare(item_key, key) != cmp::Ordering::Less,
+                Bound::Excluded(key) => compare.compare(item_key, key) == cmp::Ordering::Greater,
+                Bound::Unbounded => true,
+            };
+            node = if is_above {
+                found = Some(item);
+                Link::of::<A>(item).left()
             } else {
-                self_.link.unlink();
-            }
-            node_link.unlink();
-            self_.size.decr();
-            Some(node)
-        } else {
-            None
+                Link::of::<A>(item).right()
+            };
         }
+        found
     }
 
-    pub fn pop_back(self: Pin<&mut Self>) -> Option<NonNull<T>> {
-        let self_ = Pin::into_inner(self);
-        if let Some(node) = &mut self_.link.right_ptr {
-            let mut node = NonNull::from(node.as_mut().get_mut());
-            let node_link = A::link_mut(unsafe { node.as_mut() });
-            if let Some(left) = &node_link.left_ptr {
-                todo!()
-            } else if let Some(top) = &mut node_link.top_ptr {
-                NonNullPtr::assign_pin(&mut self_.link.right_ptr, top);
-                let top_link = A::link_mut(unsafe { top.as_mut().get_mut() });
-                top_link.right_ptr = None;
+    pub(crate) fn upper_node<A, Q, C>(&self, bound: Bound<&Q>, compare: &C) -> Option<NonNull<T>>
+    where
+        A: KeyAdapter<T, Link = Link<T, P>>,
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let mut node = self.top();
+        let mut found = None;
+        while let Some(item) = node {
+            let item_key = A::get_key(unsafe { item.as_ref() }).borrow();
+            let is_below = match bound {
+                Bound::Included(key) => compare.compare(item_key, key) != cmp::Ordering::Greater,
+                Bound::Excluded(key) => compare.compare(item_key, key) == cmp::Ordering::Less,
+                Bound::Unbounded => true,
+            };
+            node = if is_below {
+                found = Some(item);
+                Link::of::<A>(item).right()
             } else {
-                self_.link.unlink();
+                Link::of::<A>(item).left()
+            };
+        }
+        found
+    }
+
+    pub(crate) fn range<A, Q, C, R>(&self, range: R, compare: &C) -> Range<'_, T, A, P>
+    where
+        A: KeyAdapter<T, Link = Link<T, P>>,
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let front = self.lower_node::<A, Q, C>(range.start_bound(), compare);
+        let back = self.upper_node::<A, Q, C>(range.end_bound(), compare);
+        let (front, back) = match (front, back) {
+            (Some(front), Some(back)) => {
+                let front_key = A::get_key(unsafe { front.as_ref() }).borrow();
+                let back_key = A::get_key(unsafe { back.as_ref() }).borrow();
+                if compare.compare(front_key, back_key) == cmp::Ordering::Greater {
+                    (None, None)
+                } else {
+                    (Some(front), Some(back))
+                }
             }
-            node_link.unlink();
-            self_.size.decr();
-            Some(node)
-        } else {
-            None
+            _ => (None, None),
+        };
+        Range {
+            front,
+            back,
+            _marker: PhantomData,
         }
     }
 
-    pub fn front(self: Pin<&Self>) -> Option<&T> {
-        let self_ = Pin::into_inner(self);
-        if let Some(node) = &self_.link.left_ptr {
-            Some(node)
-        } else {
-            None
-        }
+    /// Moves the nodes whose keys are not less than `key` below the empty
+    /// header `other`, relinking them along a single path from the root.
+    pub(crate) fn split_off<A, Q, C>(&mut self, other: &mut Self, key: &Q, compare: &C)
+    where
+        A: KeyAdapter<T, Link = Link<T, P>>,
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        // the nodes below which the next node of each side is hung
+        let mut left_top = None;
+        let mut right_top = None;
+        let mut node = self.top();
+        while let Some(item) = node {
+            let item_key = A::get_key(unsafe { item.as_ref() }).borrow();
+            if compare.compare(item_key, key) == cmp::Ordering::Less {
+                node = Link::of::<A>(item).right();
+                self.hang::<A>(left_top, item, false);
+                left_top = Some(item);
+            } else {
+                node = Link::of::<A>(item).left();
+                other.hang::<A>(right_top, item, true);
+                right_top = Some(item);
+            }
+        }
+        match left_top {
+            Some(top) => Link::of::<A>(top).right_ptr.set(None),
+            None => self.top_ptr.set(None),
+        }
+        match right_top {
+            Some(top) => Link::of::<A>(top).left_ptr.set(None),
+            None => other.top_ptr.set(None),
+        }
+        self.reset_ends::<A>();
+        other.reset_ends::<A>();
+    }
+
+    /// Moves the nodes below `other` whose keys are missing here below this
+    /// header, linking and unlinking each the way `B` keeps its balance.
+    pub(crate) fn union_with<A, C, B>(
+        &mut self,
+        size: &mut A::Size,
+        other: &mut Self,
+        other_size: &mut A::Size,
+        compare: &C,
+    ) where
+        A: KeyAdapter<T, Link = Link<T, P>>,
+        C: Compare<A::Key>,
+        B: Balance<T, P>,
+    {
+        let mut node = other.left();
+        while let Some(item) = node {
+            node = Self::next_of::<A>(item);
+            let key = A::get_key(unsafe { item.as_ref() });
+            if let Err(slot) = self.search::<A, A::Key, C>(key, compare) {
+                B::unlink_node(other, item);
+                other_size.decr();
+                B::link_leaf(self, &slot, item);
+                size.incr();
+            }
+        }
+    }
+
+    /// Moves the nodes whose keys `other` holds if `shared`, or lacks if
+    /// not, below the empty header `into`, unlinking and linking each the
+    /// way `B` keeps its balance. The nodes come out in order, so each one
+    /// is linked right of the last without a search.
+    pub(crate) fn extract<A, C, B>(
+        &mut self,
+        size: &mut A::Size,
+        other: &Self,
+        compare: &C,
+        into: &mut Self,
+        into_size: &mut A::Size,
+        shared: bool,
+    ) where
+        A: KeyAdapter<T, Link = Link<T, P>>,
+        C: Compare<A::Key>,
+        B: Balance<T, P>,
+    {
+        assert!(
+            into.top().is_none(),
+            "the tree to extract into is not empty"
+        );
+        let mut node = self.left();
+        while let Some(item) = node {
+            node = Self::next_of::<A>(item);
+            let key = A::get_key(unsafe { item.as_ref() });
+            if other.search::<A, A::Key, C>(key, compare).is_ok() == shared {
+                B::unlink_node(self, item);
+                size.decr();
+                B::link_leaf(into, &Slot::after(into.right()), item);
+                into_size.incr();
+            }
+        }
+    }
+
+    pub(crate) fn range_mut<A, Q, C, R>(&mut self, range: R, compare: &C) -> RangeMut<'_, T, A, P>
+    where
+        A: KeyAdapter<T, Link = Link<T, P>>,
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let range = self.range::<A, Q, C, R>(range, compare);
+        RangeMut {
+            front: range.front,
+            back: range.back,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn iter<A>(&self) -> Iter<'_, T, A, P> {
+        Iter {
+            head: self,
+            tail: self,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn iter_mut<A>(&mut self) -> IterMut<'_, T, A, P> {
+        let link = self as *mut Self;
+        IterMut {
+            head: link,
+            tail: link,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn front(&self) -> Option<&T> {
+        let node = self.left()?;
+        Some(unsafe { &*node.as_ptr() })
+    }
+
+    pub(crate) fn front_mut(&mut self) -> Option<&mut T> {
+        let node = self.left()?;
+        Some(unsafe { &mut *node.as_ptr() })
+    }
+
+    pub(crate) fn back(&self) -> Option<&T> {
+        let node = self.right()?;
+        Some(unsafe { &*node.as_ptr() })
+    }
+
+    pub(crate) fn back_mut(&mut self) -> Option<&mut T> {
+        let node = self.right()?;
+        Some(unsafe { &mut *node.as_ptr() })
+    }
+
+    pub(crate) fn len<A>(&self, size: &A::Size) -> usize
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        size.len(self.iter::<A>())
+    }
+
+    pub(crate) fn is_empty<A>(&self, size: &A::Size) -> bool
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        size.is_empty(self.iter::<A>())
+    }
+
+    /// Moves the count of the nodes from `node` to the last one from `from`
+    /// to `to`. Only a size that counts walks them.
+    pub(crate) fn move_size<A>(from: &mut A::Size, to: &mut A::Size, node: Option<NonNull<T>>)
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        if A::Size::COUNTS {
+            let moved = iter::successors(node, |&item| Self::next_of::<A>(item)).count();
+            from.sub(moved);
+            to.add(moved);
+        }
+    }
+
+    /// Moves the count of every node below this header from `from` to `to`,
+    /// which a size that counts already knows without a walk.
+    pub(crate) fn move_all_size<A>(&self, from: &mut A::Size, to: &mut A::Size)
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        if A::Size::COUNTS {
+            let moved = self.len::<A>(from);
+            from.sub(moved);
+            to.add(moved);
+        }
+    }
+
+    /// Unlinks every node below the header, releasing the owned ones.
+    pub(crate) fn clear<A>(&mut self)
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        self.drain::<A>(|node| drop(unsafe { A::Pointer::from_raw(node) }));
+    }
+}
+
+/// What `unlink_node` moved: the successor spliced into the place of a node
+/// with two children, and the place that lost a node, given by its top and
+/// the child that moved up into it.
+pub(crate) struct Vacancy<T> {
+    pub(crate) next: Option<NonNull<T>>,
+    pub(crate) top: Option<NonNull<T>>,
+    pub(crate) child: Option<NonNull<T>>,
+}
+
+/// Where a search for a missing key left the tree.
+pub(crate) struct Slot<T> {
+    pub(crate) top: Option<NonNull<T>>,
+    is_left: bool,
+    is_left_end: bool,
+    is_right_end: bool,
+}
+
+impl<T> Slot<T> {
+    /// The place right of the maximum `last`, or the root of an empty tree.
+    fn after(last: Option<NonNull<T>>) -> Self {
+        Self {
+            top: last,
+            is_left: false,
+            is_left_end: last.is_none(),
+            is_right_end: true,
+        }
+    }
+}
+
+/// How a search tree built on `Link` links a leaf and unlinks a node, each
+/// time restoring its own balance.
+pub(crate) trait Balance<T, P> {
+    fn link_leaf(tree: &mut Link<T, P>, slot: &Slot<T>, node: NonNull<T>);
+
+    fn unlink_node(tree: &mut Link<T, P>, node: NonNull<T>);
+}
+
+impl<T, P> Default for Link<T, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, P> Unpin for Link<T, P> where T: Unpin {}
+
+impl<T, P> cmp::PartialEq for Link<T, P> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<T, P> cmp::Eq for Link<T, P> {}
+
+impl<T, P> cmp::PartialOrd for Link<T, P> {
+    fn partial_cmp(&self, _: &Self) -> Option<cmp::Ordering> {
+        Some(cmp::Ordering::Equal)
+    }
+}
+
+impl<T, P> cmp::Ord for Link<T, P> {
+    fn cmp(&self, _: &Self) -> cmp::Ordering {
+        cmp::Ordering::Equal
+    }
+}
+
+impl<T, P> fmt::Debug for Link<T, P>
+where
+    P: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{ top: ")?;
+        if let Some(top) = unsafe { &*self.top_ptr.as_ptr() } {
+            write!(f, "{:?}", top)?;
+        } else {
+            write!(f, "0x0")?;
+        }
+        write!(f, ", left: ")?;
+        if let Some(left) = unsafe { &*self.left_ptr.as_ptr() } {
+            write!(f, "{:?}", left)?;
+        } else {
+            write!(f, "0x0")?;
+        }
+        write!(f, ", right: ")?;
+        if let Some(right) = unsafe { &*self.right_ptr.as_ptr() } {
+            write!(f, "{:?}", right)?;
+        } else {
+            write!(f, "0x0")?;
+        }
+        write!(f, " }}")
+    }
+}
+
+/// A link that embeds a `bintree::Link`, as the balanced trees do to keep
+/// their balance data beside the three pointers.
+pub trait AsTreeLink<T>: Sized {
+    type Pointer;
+
+    fn tree_ref<A>(data: &T) -> &Link<T, Self::Pointer>
+    where
+        A: LinkAdapter<T, Link = Self>;
+
+    fn tree_mut<A>(data: &mut T) -> &mut Link<T, Self::Pointer>
+    where
+        A: LinkAdapter<T, Link = Self>;
+}
+
+/// Exposes the `bintree::Link` inside the link chosen by `A`, so the search
+/// tree algorithms here run unchanged on the nodes of the balanced trees.
+pub struct TreeLink<A>(PhantomData<A>);
+
+impl<T, A> LinkAdapter<T> for TreeLink<A>
+where
+    A: LinkAdapter<T>,
+    A::Link: AsTreeLink<T>,
+{
+    type Link = Link<T, <A::Link as AsTreeLink<T>>::Pointer>;
+    type Size = A::Size;
+    type Pointer = A::Pointer;
+
+    fn link_ref(data: &T) -> &Self::Link {
+        A::Link::tree_ref::<A>(data)
+    }
+
+    fn link_mut(data: &mut T) -> &mut Self::Link {
+        A::Link::tree_mut::<A>(data)
+    }
+}
+
+impl<T, A> KeyAdapter<T> for TreeLink<A>
+where
+    A: KeyAdapter<T>,
+    A::Link: AsTreeLink<T>,
+{
+    type Key = A::Key;
+
+    fn get_key(data: &T) -> &Self::Key {
+        A::get_key(data)
+    }
+}
+
+pub struct Iter<'a, T, A, P> {
+    head: *const Link<T, P>,
+    tail: *const Link<T, P>,
+    _marker: PhantomData<&'a A>,
+}
+
+impl<'a, T, A, P> Iterator for Iter<'a, T, A, P>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    type Item = Pin<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = unsafe { &*self.head }.next_link::<A>()?;
+        let node_link = Link::of::<A>(node);
+        if ptr::eq(node_link, self.tail) {
+            return None;
+        }
+        self.head = node_link;
+        Some(Pin::new(unsafe { &*node.as_ptr() }))
+    }
+}
+
+impl<'a, T, A, P> DoubleEndedIterator for Iter<'a, T, A, P>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = unsafe { &*self.tail }.prev_link::<A>()?;
+        let node_link = Link::of::<A>(node);
+        if ptr::eq(node_link, self.head) {
+            return None;
+        }
+        self.tail = node_link;
+        Some(Pin::new(unsafe { &*node.as_ptr() }))
+    }
+}
+
+pub struct IterMut<'a, T, A, P> {
+    head: *const Link<T, P>,
+    tail: *const Link<T, P>,
+    _marker: PhantomData<&'a A>,
+}
+
+impl<'a, T, A, P> Iterator for IterMut<'a, T, A, P>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    type Item = Pin<&'a mut T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = unsafe { &*self.head }.next_link::<A>()?;
+        let node_link = Link::of::<A>(node);
+        if ptr::eq(node_link, self.tail) {
+            return None;
+        }
+        self.head = node_link;
+        Some(Pin::new(unsafe { &mut *node.as_ptr() }))
+    }
+}
+
+impl<'a, T, A, P> DoubleEndedIterator for IterMut<'a, T, A, P>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = unsafe { &*self.tail }.prev_link::<A>()?;
+        let node_link = Link::of::<A>(node);
+        if ptr::eq(node_link, self.head) {
+            return None;
+        }
+        self.tail = node_link;
+        Some(Pin::new(unsafe { &mut *node.as_ptr() }))
+    }
+}
+
+pub struct Range<'a, T, A, P> {
+    front: Option<NonNull<T>>,
+    back: Option<NonNull<T>>,
+    _marker: PhantomData<(&'a T, &'a A, P)>,
+}
+
+impl<'a, T, A, P> Iterator for Range<'a, T, A, P>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    type Item = Pin<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.front?;
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.front = Link::next_of::<A>(node);
+        }
+        Some(Pin::new(unsafe { &*node.as_ptr() }))
+    }
+}
+
+impl<'a, T, A, P> DoubleEndedIterator for Range<'a, T, A, P>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.back?;
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.back = Link::prev_of::<A>(node);
+        }
+        Some(Pin::new(unsafe { &*node.as_ptr() }))
+    }
+}
+
+pub struct RangeMut<'a, T, A, P> {
+    front: Option<NonNull<T>>,
+    back: Option<NonNull<T>>,
+    _marker: PhantomData<(&'a mut T, &'a A, P)>,
+}
+
+impl<'a, T, A, P> Iterator for RangeMut<'a, T, A, P>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    type Item = Pin<&'a mut T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.front?;
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.front = Link::next_of::<A>(node);
+        }
+        Some(Pin::new(unsafe { &mut *node.as_ptr() }))
+    }
+}
+
+impl<'a, T, A, P> DoubleEndedIterator for RangeMut<'a, T, A, P>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.back?;
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.back = Link::prev_of::<A>(node);
+        }
+        Some(Pin::new(unsafe { &mut *node.as_ptr() }))
+    }
+}
+
+pub(crate) struct Merge<'a, T, A, P, C>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    a: Peekable<Iter<'a, T, A, P>>,
+    b: Peekable<Iter<'a, T, A, P>>,
+    compare: &'a C,
+}
+
+impl<'a, T, A, P, C> Merge<'a, T, A, P, C>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    pub(crate) fn new(a: Iter<'a, T, A, P>, b: Iter<'a, T, A, P>, compare: &'a C) -> Self {
+        Merge {
+            a: a.peekable(),
+            b: b.peekable(),
+            compare,
+        }
+    }
+}
+
+impl<'a, T, A, P, C> Iterator for Merge<'a, T, A, P, C>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    C: Compare<A::Key>,
+{
+    type Item = (Option<Pin<&'a T>>, Option<Pin<&'a T>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cmp = match (self.a.peek(), self.b.peek()) {
+            (None, None) => return None,
+            (Some(_), None) => cmp::Ordering::Less,
+            (None, Some(_)) => cmp::Ordering::Greater,
+            (Some(a), Some(b)) => self.compare.compare(A::get_key(a), A::get_key(b)),
+        };
+        Some(match cmp {
+            cmp::Ordering::Less => (self.a.next(), None),
+            cmp::Ordering::Greater => (None, self.b.next()),
+            cmp::Ordering::Equal => (self.a.next(), self.b.next()),
+        })
+    }
+}
+
+/// The elements of either tree, taken from the first one when both hold a key.
+pub struct Union<'a, T, A, P, C>(pub(crate) Merge<'a, T, A, P, C>)
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>;
+
+impl<'a, T, A, P, C> Iterator for Union<'a, T, A, P, C>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    C: Compare<A::Key>,
+{
+    type Item = Pin<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (a, b) = self.0.next()?;
+        a.or(b)
+    }
+}
+
+/// The elements of the first tree whose keys the second one also holds.
+pub struct Intersection<'a, T, A, P, C>(pub(crate) Merge<'a, T, A, P, C>)
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>;
+
+impl<'a, T, A, P, C> Iterator for Intersection<'a, T, A, P, C>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    C: Compare<A::Key>,
+{
+    type Item = Pin<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let (Some(a), Some(_)) = self.0.next()? {
+                return Some(a);
+            }
+        }
+    }
+}
+
+/// The elements of the first tree whose keys the second one lacks.
+pub struct Difference<'a, T, A, P, C>(pub(crate) Merge<'a, T, A, P, C>)
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>;
+
+impl<'a, T, A, P, C> Iterator for Difference<'a, T, A, P, C>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    C: Compare<A::Key>,
+{
+    type Item = Pin<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let (Some(a), None) = self.0.next()? {
+                return Some(a);
+            }
+        }
+    }
+}
+
+/// The elements whose keys only one of the trees holds.
+pub struct SymmetricDifference<'a, T, A, P, C>(pub(crate) Merge<'a, T, A, P, C>)
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>;
+
+impl<'a, T, A, P, C> Iterator for SymmetricDifference<'a, T, A, P, C>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    C: Compare<A::Key>,
+{
+    type Item = Pin<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let (Some(x), None) | (None, Some(x)) = self.0.next()? {
+                return Some(x);
+            }
+        }
+    }
+}
+
+pub enum Entry<'a, T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    Occupied(OccupiedEntry<'a, T, A, P, C>),
+    Vacant(VacantEntry<'a, T, A, P, C>),
+}
+
+impl<'a, T, A, P, C> Entry<'a, T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    C: Compare<A::Key>,
+{
+    /// Returns the element, inserting the one made by `f` if there is none.
+    pub fn or_insert_with<F>(self, f: F) -> Pin<&'a mut T>
+    where
+        F: FnOnce() -> A::Pointer,
+        A::Pointer: UniquePointer<T>,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(f()),
+        }
+    }
+}
+
+pub struct OccupiedEntry<'a, T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    tree: &'a mut BinTree<T, A, P, C>,
+    node: NonNull<T>,
+}
+
+impl<'a, T, A, P, C> OccupiedEntry<'a, T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    C: Compare<A::Key>,
+{
+    pub fn get(&self) -> Pin<&T> {
+        Pin::new(unsafe { self.node.as_ref() })
+    }
+
+    pub fn get_mut(&mut self) -> Pin<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        Pin::new(unsafe { self.node.as_mut() })
+    }
+
+    pub fn into_mut(mut self) -> Pin<&'a mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        Pin::new(unsafe { self.node.as_mut() })
+    }
+
+    /// Puts `item` in the place of the element and returns the latter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key of `item` does not equal the one of the element.
+    pub fn replace(self, item: A::Pointer) -> A::Pointer {
+        let item = item.into_raw();
+        self.tree.link.assert_unlinked::<A>(item);
+        let cmp = self.tree.compare.compare(
+            A::get_key(unsafe { item.as_ref() }),
+            A::get_key(unsafe { self.node.as_ref() }),
+        );
+        if cmp != cmp::Ordering::Equal {
+            drop(unsafe { A::Pointer::from_raw(item) });
+            panic!("the key does not match the entry");
+        }
+        self.tree.link.replace_node::<A>(self.node, item);
+        unsafe { A::Pointer::from_raw(self.node) }
+    }
+
+    pub fn remove(self) -> A::Pointer {
+        self.tree.link.unlink_node::<A>(self.node);
+        self.tree.size.decr();
+        unsafe { A::Pointer::from_raw(self.node) }
+    }
+}
+
+pub struct VacantEntry<'a, T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    tree: &'a mut BinTree<T, A, P, C>,
+    slot: Slot<T>,
+}
+
+impl<'a, T, A, P, C> VacantEntry<'a, T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    C: Compare<A::Key>,
+{
+    /// Links `item` without searching again, in O(1) plus the rebalancing.
+    ///
+    /// The key of `item` must fall between the neighbours of the vacant
+    /// place, that is, it must be found there; otherwise the tree is left
+    /// out of order.
+    ///
+    /// # Panics
+    ///
+    /// Debug builds check the key against the neighbours, which takes
+    /// O(height), and panic if it does not match the entry.
+    pub fn insert(self, item: A::Pointer) -> Pin<&'a mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        let mut item = item.into_raw();
+        self.tree.link.assert_unlinked::<A>(item);
+        if cfg!(debug_assertions) {
+            let (prev, next) = match self.slot.top {
+                None => (None, None),
+                Some(top) if self.slot.is_left => (Link::prev_of::<A>(top), Some(top)),
+                Some(top) => (Some(top), Link::next_of::<A>(top)),
+            };
+            let less = |a: NonNull<T>, b: NonNull<T>| {
+                let (a, b) = unsafe { (a.as_ref(), b.as_ref()) };
+                self.tree.compare.compare(A::get_key(a), A::get_key(b)) == cmp::Ordering::Less
+            };
+            if !prev.is_none_or(|prev| less(prev, item))
+                || !next.is_none_or(|next| less(item, next))
+            {
+                drop(unsafe { A::Pointer::from_raw(item) });
+                panic!("the key does not match the entry");
+            }
+        }
+        self.tree.link.link_leaf::<A>(&self.slot, item);
+        self.tree.size.incr();
+        Pin::new(unsafe { item.as_mut() })
+    }
+}
+
+#[derive(Debug)]
+pub struct BinTree<T, A, P = NonNull<T>, C = NaturalOrder>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    size: A::Size,
+    link: Link<T, P>,
+    compare: C,
+}
+
+impl<T, A, P> BinTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    pub fn new(adapter: A) -> Self {
+        Self::with_comparator(adapter, NaturalOrder)
+    }
+}
+
+impl<T, A, P, C> BinTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    /// Creates a tree that orders its keys by `compare` instead of `Ord`.
+    ///
+    /// A closure compares `A::Key` only, so lookups then take `&A::Key`
+    /// rather than a borrowed form of it; see [`Compare`].
+    pub fn with_comparator(_: A, compare: C) -> Self {
+        Self {
+            size: Default::default(),
+            link: Link::new(),
+            compare,
+        }
+    }
+}
+
+impl<T, A, P, C> BinTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    C: Compare<A::Key>,
+{
+    pub fn get<Q>(self: Pin<&Self>, key: &Q) -> Option<Pin<&T>>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let node = Pin::get_ref(self).search(key).ok()?;
+        Some(Pin::new(unsafe { &*node.as_ptr() }))
+    }
+
+    pub fn get_mut<Q>(self: Pin<&mut Self>, key: &Q) -> Option<Pin<&mut T>>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+        A::Pointer: UniquePointer<T>,
+    {
+        let node = Pin::get_ref(self.into_ref()).search(key).ok()?;
+        Some(Pin::new(unsafe { &mut *node.as_ptr() }))
+    }
+
+    pub fn insert(self: Pin<&mut Self>, item: A::Pointer) -> Option<A::Pointer> {
+        let item = item.into_raw();
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        self_.link.assert_unlinked::<A>(item);
+        match self_.search(A::get_key(unsafe { item.as_ref() })) {
+            Ok(_) => Some(unsafe { A::Pointer::from_raw(item) }),
+            Err(slot) => {
+                self_.link.link_leaf::<A>(&slot, item);
+                self_.size.incr();
+                None
+            }
+        }
+    }
+
+    /// Finds the element for `key`, or the place where it would be linked.
+    pub fn entry<Q>(self: Pin<&mut Self>, key: &Q) -> Entry<'_, T, A, P, C>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let tree = unsafe { Pin::get_unchecked_mut(self) };
+        match tree.search(key) {
+            Ok(node) => Entry::Occupied(OccupiedEntry { tree, node }),
+            Err(slot) => Entry::Vacant(VacantEntry { tree, slot }),
+        }
+    }
+
+    fn search<Q>(&self, key: &Q) -> Result<NonNull<T>, Slot<T>>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        self.link.search::<A, Q, C>(key, &self.compare)
+    }
+
+    /// Returns the first element whose key lies above `bound`.
+    pub fn lower_bound<Q>(self: Pin<&Self>, bound: Bound<&Q>) -> Option<Pin<&T>>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let node = Pin::get_ref(self).lower_node(bound)?;
+        Some(Pin::new(unsafe { &*node.as_ptr() }))
+    }
+
+    /// Returns the last element whose key lies below `bound`.
+    pub fn upper_bound<Q>(self: Pin<&Self>, bound: Bound<&Q>) -> Option<Pin<&T>>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let node = Pin::get_ref(self).upper_node(bound)?;
+        Some(Pin::new(unsafe { &*node.as_ptr() }))
+    }
+
+    /// Returns the greatest element whose key is not greater than `key`.
+    pub fn find_floor<Q>(self: Pin<&Self>, key: &Q) -> Option<Pin<&T>>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        self.upper_bound(Bound::Included(key))
+    }
+
+    /// Returns the least element whose key is not less than `key`.
+    pub fn find_ceiling<Q>(self: Pin<&Self>, key: &Q) -> Option<Pin<&T>>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        self.lower_bound(Bound::Included(key))
+    }
+
+    pub fn range<Q, R>(self: Pin<&Self>, range: R) -> Range<'_, T, A, P>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let self_ = Pin::get_ref(self);
+        self_.link.range::<A, Q, C, R>(range, &self_.compare)
+    }
+
+    fn lower_node<Q>(&self, bound: Bound<&Q>) -> Option<NonNull<T>>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        self.link.lower_node::<A, Q, C>(bound, &self.compare)
+    }
+
+    fn upper_node<Q>(&self, bound: Bound<&Q>) -> Option<NonNull<T>>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        self.link.upper_node::<A, Q, C>(bound, &self.compare)
+    }
+
+    pub fn union<'a>(self: Pin<&'a Self>, other: Pin<&'a Self>) -> Union<'a, T, A, P, C> {
+        Union(self.merge(other))
+    }
+
+    pub fn intersection<'a>(
+        self: Pin<&'a Self>,
+        other: Pin<&'a Self>,
+    ) -> Intersection<'a, T, A, P, C> {
+        Intersection(self.merge(other))
+    }
+
+    pub fn difference<'a>(self: Pin<&'a Self>, other: Pin<&'a Self>) -> Difference<'a, T, A, P, C> {
+        Difference(self.merge(other))
+    }
+
+    pub fn symmetric_difference<'a>(
+        self: Pin<&'a Self>,
+        other: Pin<&'a Self>,
+    ) -> SymmetricDifference<'a, T, A, P, C> {
+        SymmetricDifference(self.merge(other))
+    }
+
+    fn merge<'a>(self: Pin<&'a Self>, other: Pin<&'a Self>) -> Merge<'a, T, A, P, C> {
+        Merge::new(self.iter(), other.iter(), &Pin::get_ref(self).compare)
+    }
+
+    /// Moves the elements of `other` whose keys are missing here into this tree.
+    ///
+    /// Afterwards `other` holds only the elements whose keys both trees held.
+    pub fn union_with(self: Pin<&mut Self>, other: Pin<&mut Self>) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let other = unsafe { Pin::get_unchecked_mut(other) };
+        self_.link.union_with::<A, C, Self>(
+            &mut self_.size,
+            &mut other.link,
+            &mut other.size,
+            &self_.compare,
+        );
+    }
+
+    /// Moves the elements whose keys `other` also holds into the empty tree `into`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `into` is not empty.
+    pub fn extract_intersection(self: Pin<&mut Self>, other: Pin<&Self>, into: Pin<&mut Self>) {
+        self.extract(other, into, true)
+    }
+
+    /// Moves the elements whose keys `other` lacks into the empty tree `into`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `into` is not empty.
+    pub fn extract_difference(self: Pin<&mut Self>, other: Pin<&Self>, into: Pin<&mut Self>) {
+        self.extract(other, into, false)
+    }
+
+    fn extract(self: Pin<&mut Self>, other: Pin<&Self>, into: Pin<&mut Self>, shared: bool) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let other = Pin::get_ref(other);
+        let into = unsafe { Pin::get_unchecked_mut(into) };
+        self_.link.extract::<A, C, Self>(
+            &mut self_.size,
+            &other.link,
+            &self_.compare,
+            &mut into.link,
+            &mut into.size,
+            shared,
+        );
+        // the elements were linked as a vine, which is folded in one pass
+        into.link.rebalance::<A>();
+    }
+
+    /// Links elements in increasing key order, all greater than the keys
+    /// here, and leaves the whole tree balanced, in time linear in its size.
+    ///
+    /// The elements are chained into a vine below the maximum as they come
+    /// and the tree is rebalanced afterwards (Day-Stout-Warren), so their
+    /// number need not be known in advance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an element is not greater than the one before it. The
+    /// elements before it are linked all the same.
+    pub fn extend_sorted<I>(self: Pin<&mut Self>, iter: I)
+    where
+        I: IntoIterator<Item = A::Pointer>,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let key = |node: NonNull<T>| A::get_key(unsafe { node.as_ref() });
+        let back = self_.link.right();
+        let mut first = self_.link.left();
+        let mut tail = back;
+        let mut unsorted = None;
+        for item in iter {
+            let item = item.into_raw();
+            if tail.is_some_and(|prev| {
+                self_.compare.compare(key(prev), key(item)) != cmp::Ordering::Less
+            }) {
+                unsorted = Some(unsafe { A::Pointer::from_raw(item) });
+                break;
+            }
+            self_.link.assert_unlinked::<A>(item);
+            self_.link.hang::<A>(tail, item, false);
+            first = first.or(Some(item));
+            tail = Some(item);
+            self_.size.incr();
+        }
+
+        if tail != back {
+            self_.link.set_ends(first, tail);
+            self_.link.rebalance::<A>();
+        }
+        assert!(unsorted.is_none(), "the elements are not sorted");
+    }
+
+    /// Moves every element whose key is not less than `key` into the empty tree `other`.
+    ///
+    /// Nodes are relinked along a single path from the root. A `LiterateSize`
+    /// has to count the moved elements, which makes the split O(k) in their
+    /// number; with a `NumerateSize` it takes O(height).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is not empty.
+    pub fn split_off<Q>(self: Pin<&mut Self>, key: &Q, other: Pin<&mut Self>)
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let other = unsafe { Pin::get_unchecked_mut(other) };
+        assert!(
+            other.link.top().is_none(),
+            "the tree to split into is not empty"
+        );
+        self_
+            .link
+            .split_off::<A, Q, C>(&mut other.link, key, &self_.compare);
+
+        Link::move_size::<A>(&mut self_.size, &mut other.size, other.link.left());
+    }
+
+    /// Moves every element of `other` into this tree, hanging its root below
+    /// the minimum or maximum element here. The sizes move over in one step,
+    /// so this takes O(1).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys of one tree are not all less than those of the other.
+    pub fn append(self: Pin<&mut Self>, other: Pin<&mut Self>) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let other = unsafe { Pin::get_unchecked_mut(other) };
+        let (Some(other_top), Some(other_first), Some(other_last)) =
+            (other.link.top(), other.link.left(), other.link.right())
+        else {
+            return;
+        };
+        let key = |node: NonNull<T>| A::get_key(unsafe { node.as_ref() });
+        let top = match (self_.link.left(), self_.link.right()) {
+            (Some(first), Some(last)) => {
+                let is_less = |a, b| self_.compare.compare(key(a), key(b)) == cmp::Ordering::Less;
+                if is_less(last, other_first) {
+                    Some((last, false))
+                } else if is_less(other_last, first) {
+                    Some((first, true))
+                } else {
+                    panic!("the keys of the appended tree overlap");
+                }
+            }
+            _ => None,
+        };
+
+        other
+            .link
+            .move_all_size::<A>(&mut other.size, &mut self_.size);
+        other.link.unlink();
+        let is_left = top.is_some_and(|(_, is_left)| is_left);
+        self_
+            .link
+            .hang::<A>(top.map(|(top, _)| top), other_top, is_left);
+        if top.is_none() || is_left {
+            NonNullPtr::assign(&self_.link.left_ptr, other_first);
+        }
+        if top.is_none() || !is_left {
+            NonNullPtr::assign(&self_.link.right_ptr, other_last);
+        }
+    }
+
+    pub fn remove<Q>(self: Pin<&mut Self>, key: &Q) -> Option<A::Pointer>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let mut node = self_.link.top();
+        while let Some(item) = node {
+            let item_key = A::get_key(unsafe { item.as_ref() }).borrow();
+            node = match self_.compare.compare(key, item_key) {
+                cmp::Ordering::Equal => {
+                    self_.link.unlink_node::<A>(item);
+                    self_.size.decr();
+                    return Some(unsafe { A::Pointer::from_raw(item) });
+                }
+                cmp::Ordering::Less => Link::of::<A>(item).left(),
+                cmp::Ordering::Greater => Link::of::<A>(item).right(),
+            };
+        }
+        None
+    }
+}
+
+impl<T, A, P, C> BinTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    pub fn pop_front(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.left()?;
+        let node_link = Link::of::<A>(node);
+        if let Some(right) = node_link.right() {
+            // the right subtree takes the place of node, and its first node becomes the front
+            let top = node_link.top();
+            NonNullPtr::assign_raw(&Link::of::<A>(right).top_ptr, top);
+            self_.link.replace_child::<A>(top, node, Some(right));
+            NonNullPtr::assign(&self_.link.left_ptr, Link::first_of::<A>(right));
+        } else if let Some(top) = node_link.top() {
+            NonNullPtr::assign(&self_.link.left_ptr, top);
+            Link::of::<A>(top).left_ptr.set(None);
+        } else {
+            self_.link.unlink();
+        }
+        node_link.unlink();
+        self_.size.decr();
+        Some(unsafe { A::Pointer::from_raw(node) })
+    }
+
+    pub fn pop_back(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.right()?;
+        let node_link = Link::of::<A>(node);
+        if let Some(left) = node_link.left() {
+            // the left subtree takes the place of node, and its last node becomes the back
+            let top = node_link.top();
+            NonNullPtr::assign_raw(&Link::of::<A>(left).top_ptr, top);
+            self_.link.replace_child::<A>(top, node, Some(left));
+            NonNullPtr::assign(&self_.link.right_ptr, Link::last_of::<A>(left));
+        } else if let Some(top) = node_link.top() {
+            NonNullPtr::assign(&self_.link.right_ptr, top);
+            Link::of::<A>(top).right_ptr.set(None);
+        } else {
+            self_.link.unlink();
+        }
+        node_link.unlink();
+        self_.size.decr();
+        Some(unsafe { A::Pointer::from_raw(node) })
+    }
+
+    pub fn front(self: Pin<&Self>) -> Option<&T> {
+        Pin::get_ref(self).link.front()
+    }
+
+    pub fn front_mut(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.front_mut()
+    }
+
+    pub fn back(self: Pin<&Self>) -> Option<&T> {
+        Pin::get_ref(self).link.back()
+    }
+
+    pub fn back_mut(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.back_mut()
+    }
+
+    pub fn iter(self: Pin<&Self>) -> Iter<'_, T, A, P> {
+        Pin::get_ref(self).link.iter()
     }
 
-    pub fn front_mut(self: Pin<&mut Self>) -> Option<&mut T> {
-        let self_ = Pin::into_inner(self);
-        if let Some(node) = &mut self_.link.left_ptr {
-            Some(node)
-        } else {
-            None
-        }
+    pub fn iter_mut(self: Pin<&mut Self>) -> IterMut<'_, T, A, P>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.iter_mut()
     }
 
-    pub fn back(self: Pin<&Self>) -> Option<&T> {
-        let self_ = Pin::into_inner(self);
-        if let Some(node) = &self_.link.right_ptr {
-            Some(node)
-        } else {
-            None
-        }
+    pub fn len(self: Pin<&Self>) -> usize {
+        self.size.len(self.iter())
     }
 
-    pub fn back_mut(self: Pin<&mut Self>) -> Option<&mut T> {
-        let self_ = Pin::into_inner(self);
-        if let Some(node) = &mut self_.link.right_ptr {
-            Some(node)
-        } else {
-            None
+    pub fn is_empty(self: Pin<&Self>) -> bool {
+        self.size.is_empty(self.iter())
+    }
+
+    /// Returns the number of nodes on the longest path from the root.
+    pub fn height(self: Pin<&Self>) -> usize {
+        let Some(mut node) = self.link.top() else {
+            return 0;
+        };
+        // walks the tree through the parent links, so deep trees need no stack
+        let mut from = None;
+        let mut depth = 1;
+        let mut height = 1;
+        loop {
+            let link = Link::of::<A>(node);
+            let down = if from == link.top() {
+                link.left().or(link.right())
+            } else if from == link.left() {
+                link.right()
+            } else {
+                None
+            };
+            from = Some(node);
+            if let Some(child) = down {
+                node = child;
+                depth += 1;
+                height = height.max(depth);
+            } else if let Some(top) = link.top() {
+                node = top;
+                depth -= 1;
+            } else {
+                return height;
+            }
         }
     }
 
-    pub fn remove(self: Pin<&mut Self>, _data: &T) -> Option<NonNull<T>> {
-        todo!()
+    /// Rebuilds the tree into a balanced shape in linear time and constant
+    /// space (Day-Stout-Warren), rotating the existing nodes in place.
+    pub fn rebalance(self: Pin<&mut Self>) {
+        unsafe { Pin::get_unchecked_mut(self) }
+            .link
+            .rebalance::<A>();
     }
 
-    pub fn iter(self: Pin<&Self>) -> Iter<T, A, P> {
-        let self_ = Pin::into_inner(self);
-        Iter {
-            link: &self_.link,
-            _marker: PhantomData,
-        }
+    /// Unlinks every element, releasing the owned ones.
+    pub fn clear(self: Pin<&mut Self>) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        self_
+            .link
+            .drain::<A>(|node| drop(unsafe { A::Pointer::from_raw(node) }));
+        self_.size = Default::default();
     }
+}
 
-    pub fn iter_mut(self: Pin<&mut Self>) -> IterMut<T, A, P> {
-        let self_ = Pin::into_inner(self);
-        IterMut {
-            link: &mut self_.link,
-            _marker: PhantomData,
-        }
+impl<T, A, P, C> Default for BinTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>> + Default,
+    C: Default,
+{
+    fn default() -> Self {
+        Self::with_comparator(A::default(), C::default())
     }
+}
 
-    pub fn len(self: Pin<&Self>) -> usize {
-        self.size.len(self.iter())
+impl<T, A, P, C> Balance<T, P> for BinTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn link_leaf(tree: &mut Link<T, P>, slot: &Slot<T>, node: NonNull<T>) {
+        tree.link_leaf::<A>(slot, node);
     }
 
-    pub fn is_empty(self: Pin<&Self>) -> bool {
-        self.size.is_empty(self.iter())
+    fn unlink_node(tree: &mut Link<T, P>, node: NonNull<T>) {
+        tree.unlink_node::<A>(node);
     }
 }
 
-impl<T, A, P> Default for BinTree<T, A, P>
+impl<T, A, P, C> Drop for BinTree<T, A, P, C>
 where
-    A: LinkAdapter<T> + Default,
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
 {
-    fn default() -> Self {
-        Self::new(A::default())
+    fn drop(&mut self) {
+        if mem::needs_drop::<A::Pointer>() {
+            unsafe { Pin::new_unchecked(self) }.clear();
+        }
     }
 }
 
-impl<T, A, P> Unpin for BinTree<T, A, P>
+impl<T, A, P, C> Unpin for BinTree<T, A, P, C>
 where
     T: Unpin,
-    A: LinkAdapter<T>,
+    P: Pointer<T> + Unpin,
+    A: LinkAdapter<T, Link = Link<T, P>>,
 {
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::adapter::NumerateSize;
+    use crate::adapter::{LiterateSize, NumerateSize};
+    use crate::intrusive_adapter;
     use std::fmt::Formatter;
+    use std::panic;
+    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[derive(PartialEq, Eq, PartialOrd, Ord)]
     struct X {
@@ -576,20 +2012,8 @@ mod test {
         }
     }
 
-    #[derive(Debug)]
-    struct XLink;
-
-    impl LinkAdapter<X> for XLink {
-        type Link = Link<X>;
-        type Size = NumerateSize;
-
-        fn link_ref(data: &X) -> &Self::Link {
-            &data.link
-        }
-
-        fn link_mut(data: &mut X) -> &mut Self::Link {
-            &mut data.link
-        }
+    intrusive_adapter! {
+        XLink = X { link: Link<X>, key = self } with NumerateSize;
     }
 
     #[test]
@@ -726,4 +2150,793 @@ mod test {
         let item = X::from(tree.as_mut().pop_front()).unwrap();
         assert_eq!(item.data, 2);
     }
+
+    fn key(data: i32) -> X {
+        X {
+            data,
+            link: Link::new(),
+        }
+    }
+
+    // checks the parent links and the ordering of the subtree
+    fn check(node: Option<NonNull<X>>, top: Option<NonNull<X>>) {
+        if let Some(node) = node {
+            let link = Link::of::<XLink>(node);
+            assert_eq!(link.top(), top);
+            if let Some(left) = link.left() {
+                assert!(unsafe { left.as_ref().data < node.as_ref().data });
+            }
+            if let Some(right) = link.right() {
+                assert!(unsafe { right.as_ref().data > node.as_ref().data });
+            }
+            check(link.left(), Some(node));
+            check(link.right(), Some(node));
+        }
+    }
+
+    #[test]
+    fn test_pop_inner_child() {
+        let mut tree = Box::pin(BinTree::new(XLink));
+        for i in [4, 1, 3, 2, 6, 7, 5] {
+            tree.as_mut().insert(X::new(i));
+        }
+        //     4
+        //  1     6
+        //    3  5 7
+        //   2
+        let item = X::from(tree.as_mut().pop_front()).unwrap();
+        assert_eq!(item.data, 1);
+        check(tree.link.top(), None);
+        assert_eq!(tree.as_ref().front().unwrap().data, 2);
+
+        let item = X::from(tree.as_mut().pop_back()).unwrap();
+        assert_eq!(item.data, 7);
+        let item = X::from(tree.as_mut().pop_back()).unwrap();
+        assert_eq!(item.data, 6);
+        check(tree.link.top(), None);
+        assert_eq!(tree.as_ref().back().unwrap().data, 5);
+
+        let mut it = tree.as_ref().iter();
+        assert_eq!(it.next().unwrap().data, 2);
+        assert_eq!(it.next().unwrap().data, 3);
+        assert_eq!(it.next().unwrap().data, 4);
+        assert_eq!(it.next().unwrap().data, 5);
+        assert_eq!(it.next(), None);
+
+        // the root only has a left subtree
+        let item = X::from(tree.as_mut().pop_back()).unwrap();
+        assert_eq!(item.data, 5);
+        let item = X::from(tree.as_mut().pop_back()).unwrap();
+        assert_eq!(item.data, 4);
+        check(tree.link.top(), None);
+        assert_eq!(tree.as_ref().back().unwrap().data, 3);
+        let item = X::from(tree.as_mut().pop_front()).unwrap();
+        assert_eq!(item.data, 2);
+        let item = X::from(tree.as_mut().pop_front()).unwrap();
+        assert_eq!(item.data, 3);
+        assert!(tree.as_ref().is_empty());
+        assert_eq!(tree.as_mut().pop_front(), None);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut tree = Box::pin(BinTree::new(XLink));
+        for i in [50, 30, 70, 20, 40, 60, 80, 35, 45, 65] {
+            tree.as_mut().insert(X::new(i));
+        }
+        //          50
+        //     30        70
+        //   20  40    60  80
+        //     35  45    65
+        assert_eq!(tree.as_ref().get(&key(45)).unwrap().data, 45);
+        assert_eq!(tree.as_ref().get(&key(65)).unwrap().data, 65);
+        assert_eq!(tree.as_ref().get(&key(66)), None);
+
+        // leaf
+        let item = X::from(tree.as_mut().remove(&key(35))).unwrap();
+        assert_eq!(item.data, 35);
+        check(tree.link.top(), None);
+        // single child
+        let item = X::from(tree.as_mut().remove(&key(60))).unwrap();
+        assert_eq!(item.data, 60);
+        check(tree.link.top(), None);
+        // two children, the successor is the right child
+        let item = X::from(tree.as_mut().remove(&key(30))).unwrap();
+        assert_eq!(item.data, 30);
+        check(tree.link.top(), None);
+        // two children, the successor is deeper in the right subtree
+        let item = X::from(tree.as_mut().remove(&key(50))).unwrap();
+        assert_eq!(item.data, 50);
+        check(tree.link.top(), None);
+        // missing
+        assert_eq!(tree.as_mut().remove(&key(50)), None);
+
+        let mut it = tree.as_ref().iter();
+        for data in [20, 40, 45, 65, 70, 80] {
+            assert_eq!(it.next().unwrap().data, data);
+        }
+        assert_eq!(it.next(), None);
+        assert_eq!(tree.as_ref().len(), 6);
+
+        // front and back
+        let item = X::from(tree.as_mut().remove(&key(20))).unwrap();
+        assert_eq!(item.data, 20);
+        assert_eq!(tree.as_ref().front().unwrap().data, 40);
+        let item = X::from(tree.as_mut().remove(&key(80))).unwrap();
+        assert_eq!(item.data, 80);
+        assert_eq!(tree.as_ref().back().unwrap().data, 70);
+        check(tree.link.top(), None);
+
+        for data in [65, 40, 70, 45] {
+            let item = X::from(tree.as_mut().remove(&key(data))).unwrap();
+            assert_eq!(item.data, data);
+            check(tree.link.top(), None);
+        }
+        assert!(tree.as_ref().is_empty());
+        assert_eq!(tree.as_ref().front(), None);
+        assert_eq!(tree.as_ref().back(), None);
+    }
+
+    #[test]
+    fn test_iter_rev() {
+        let mut tree = Box::pin(BinTree::new(XLink));
+        for i in [50, 30, 70, 20, 40, 60, 80, 35, 45, 65] {
+            tree.as_mut().insert(X::new(i));
+        }
+        //          50
+        //     30        70
+        //   20  40    60  80
+        //     35  45    65
+        let items: Vec<_> = tree.as_ref().iter().rev().map(|x| x.data).collect();
+        assert_eq!(items, [80, 70, 65, 60, 50, 45, 40, 35, 30, 20]);
+
+        let mut it = tree.as_ref().iter();
+        assert_eq!(it.next().unwrap().data, 20);
+        assert_eq!(it.next_back().unwrap().data, 80);
+        assert_eq!(it.next_back().unwrap().data, 70);
+        assert_eq!(it.next().unwrap().data, 30);
+        assert_eq!(it.next().unwrap().data, 35);
+        assert_eq!(it.next_back().unwrap().data, 65);
+        assert_eq!(it.next_back().unwrap().data, 60);
+        assert_eq!(it.next().unwrap().data, 40);
+        assert_eq!(it.next().unwrap().data, 45);
+        assert_eq!(it.next_back().unwrap().data, 50);
+        assert_eq!(it.next(), None);
+        assert_eq!(it.next_back(), None);
+
+        for mut x in tree.as_mut().iter_mut().rev() {
+            x.data += 1;
+        }
+        let mut it = tree.as_mut().iter_mut();
+        assert_eq!(it.next_back().unwrap().data, 81);
+        assert_eq!(it.next().unwrap().data, 21);
+        assert_eq!(it.by_ref().count(), 8);
+        assert!(it.next_back().is_none());
+
+        while X::from(tree.as_mut().pop_front()).is_some() {}
+        let mut it = tree.as_ref().iter();
+        assert_eq!(it.next_back(), None);
+        assert_eq!(it.next(), None);
+    }
+
+    struct W {
+        data: i32,
+        // every live element holds a clone, so the count tracks the releases
+        _alive: Rc<()>,
+        link: Link<Self>,
+    }
+
+    intrusive_adapter! {
+        WLink = W { link: Link<W>, key = data: i32 } with NumerateSize, Box<W>;
+    }
+
+    #[test]
+    fn test_owning() {
+        let alive = Rc::new(());
+        let new = |data| {
+            Box::new(W {
+                data,
+                _alive: alive.clone(),
+                link: Link::new(),
+            })
+        };
+        let mut tree = Box::pin(BinTree::new(WLink));
+        for data in [4, 2, 6, 1, 3, 5, 7] {
+            assert!(tree.as_mut().insert(new(data)).is_none());
+        }
+        assert_eq!(Rc::strong_count(&alive), 8);
+        let dup = tree.as_mut().insert(new(4)).unwrap();
+        assert_eq!(dup.data, 4);
+        assert!(!dup.link.is_linked());
+        drop(dup);
+        let item = tree.as_mut().pop_front().unwrap();
+        assert_eq!(item.data, 1);
+        drop(item);
+        assert_eq!(Rc::strong_count(&alive), 7);
+        tree.as_mut().clear();
+        assert!(tree.as_ref().is_empty());
+        assert_eq!(Rc::strong_count(&alive), 1);
+
+        for data in [2, 1, 3] {
+            tree.as_mut().insert(new(data));
+        }
+        let item = tree.as_mut().remove(&2).unwrap();
+        assert_eq!(item.data, 2);
+        assert_eq!(tree.as_ref().len(), 2);
+        drop(item);
+        drop(tree);
+        assert_eq!(Rc::strong_count(&alive), 1);
+    }
+
+    intrusive_adapter! {
+        XArcLink = X { link: Link<X>, key = data: i32 } with NumerateSize, Arc<X>;
+    }
+
+    #[test]
+    // the links are cells, so the elements stay on this thread
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn test_owning_arc() {
+        let items: Vec<_> = [4, 2, 6, 1, 3, 5, 7]
+            .into_iter()
+            .map(|data| {
+                Arc::new(X {
+                    data,
+                    link: Link::new(),
+                })
+            })
+            .collect();
+        let mut tree = Box::pin(BinTree::new(XArcLink));
+        for item in &items {
+            assert!(tree.as_mut().insert(item.clone()).is_none());
+        }
+        assert!(items.iter().all(|x| Arc::strong_count(x) == 2));
+        assert!(items.iter().all(|x| x.link.is_linked()));
+        let data: Vec<_> = tree.as_ref().iter().map(|x| x.data).collect();
+        assert_eq!(data, [1, 2, 3, 4, 5, 6, 7]);
+        let item = tree.as_mut().remove(&4).unwrap();
+        assert!(Arc::ptr_eq(&item, &items[0]));
+        drop(item);
+        assert_eq!(Arc::strong_count(&items[0]), 1);
+        assert!(!items[0].link.is_linked());
+        tree.as_mut().clear();
+        assert!(items.iter().all(|x| Arc::strong_count(x) == 1));
+        assert!(items.iter().all(|x| !x.link.is_linked()));
+
+        for item in &items {
+            tree.as_mut().insert(item.clone());
+        }
+        drop(tree);
+        assert!(items.iter().all(|x| Arc::strong_count(x) == 1));
+        assert!(items.iter().all(|x| !x.link.is_linked()));
+    }
+
+    struct Z {
+        name: String,
+        link: Link<Self>,
+    }
+
+    impl Z {
+        fn new(name: &str) -> Box<Self> {
+            Box::new(Z {
+                name: name.to_string(),
+                link: Link::new(),
+            })
+        }
+    }
+
+    intrusive_adapter! {
+        ZLink = Z { link: Link<Z>, key = name: String } with NumerateSize, Box<Z>;
+    }
+
+    #[test]
+    fn test_key_adapter() {
+        let mut tree = Box::pin(BinTree::new(ZLink));
+        for name in ["m", "c", "x", "a", "e"] {
+            assert!(tree.as_mut().insert(Z::new(name)).is_none());
+        }
+        assert_eq!(tree.as_mut().insert(Z::new("e")).unwrap().name, "e");
+
+        assert_eq!(tree.as_ref().get("c").unwrap().name, "c");
+        assert!(tree.as_ref().get("b").is_none());
+        assert!(BinTree::get_mut(tree.as_mut(), "x").is_some());
+        assert_eq!(tree.as_mut().remove("m").unwrap().name, "m");
+        assert!(tree.as_mut().remove("m").is_none());
+        let names: Vec<_> = tree
+            .as_ref()
+            .iter()
+            .map(|z| z.get_ref().name.as_str())
+            .collect();
+        assert_eq!(names, ["a", "c", "e", "x"]);
+    }
+
+    struct Direction {
+        descending: bool,
+    }
+
+    impl<K> Compare<K> for Direction
+    where
+        K: cmp::Ord + ?Sized,
+    {
+        fn compare(&self, a: &K, b: &K) -> cmp::Ordering {
+            if self.descending {
+                b.cmp(a)
+            } else {
+                a.cmp(b)
+            }
+        }
+    }
+
+    #[test]
+    fn test_comparator() {
+        fn names<C>(tree: Pin<&BinTree<Z, ZLink, NonNull<Z>, C>>) -> Vec<String> {
+            tree.iter().map(|z| z.get_ref().name.clone()).collect()
+        }
+
+        for descending in [false, true] {
+            let mut tree = Box::pin(BinTree::with_comparator(ZLink, Direction { descending }));
+            for name in ["m", "c", "x", "a"] {
+                assert!(tree.as_mut().insert(Z::new(name)).is_none());
+            }
+            assert!(tree.as_mut().insert(Z::new("c")).is_some());
+            assert_eq!(tree.as_ref().get("x").unwrap().name, "x");
+            assert_eq!(tree.as_mut().remove("a").unwrap().name, "a");
+            let mut expected = ["c", "m", "x"];
+            if descending {
+                expected.reverse();
+            }
+            assert_eq!(names(tree.as_ref()), expected);
+        }
+
+        let by_len = |a: &String, b: &String| a.len().cmp(&b.len());
+        let mut tree = Box::pin(BinTree::with_comparator(ZLink, by_len));
+        for name in ["ccc", "a", "bb"] {
+            assert!(tree.as_mut().insert(Z::new(name)).is_none());
+        }
+        assert_eq!(tree.as_mut().insert(Z::new("zz")).unwrap().name, "zz");
+        assert_eq!(tree.as_ref().get(&"xyz".to_string()).unwrap().name, "ccc");
+        assert_eq!(names(tree.as_ref()), ["a", "bb", "ccc"]);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut tree = Box::pin(BinTree::new(XLink));
+        for i in [50, 30, 70, 20, 40, 60, 80] {
+            tree.as_mut().insert(X::new(i));
+        }
+        let data = |x: Option<Pin<&X>>| x.map(|x| x.data);
+        let tree_ = tree.as_ref();
+        assert_eq!(data(tree_.lower_bound(Bound::Included(&key(40)))), Some(40));
+        assert_eq!(data(tree_.lower_bound(Bound::Excluded(&key(40)))), Some(50));
+        assert_eq!(data(tree_.lower_bound(Bound::Included(&key(45)))), Some(50));
+        assert_eq!(data(tree_.lower_bound(Bound::Excluded(&key(80)))), None);
+        assert_eq!(data(tree_.lower_bound(Bound::Unbounded)), Some(20));
+        assert_eq!(data(tree_.upper_bound(Bound::Included(&key(40)))), Some(40));
+        assert_eq!(data(tree_.upper_bound(Bound::Excluded(&key(40)))), Some(30));
+        assert_eq!(data(tree_.upper_bound(Bound::Excluded(&key(20)))), None);
+        assert_eq!(data(tree_.upper_bound(Bound::Unbounded)), Some(80));
+        assert_eq!(data(tree_.find_floor(&key(65))), Some(60));
+        assert_eq!(data(tree_.find_floor(&key(10))), None);
+        assert_eq!(data(tree_.find_ceiling(&key(65))), Some(70));
+        assert_eq!(data(tree_.find_ceiling(&key(90))), None);
+
+        while X::from(tree.as_mut().pop_front()).is_some() {}
+    }
+
+    #[test]
+    fn test_range() {
+        let mut tree = Box::pin(BinTree::new(XLink));
+        for i in [50, 30, 70, 20, 40, 60, 80, 35, 45, 65] {
+            tree.as_mut().insert(X::new(i));
+        }
+        let data = |it: Range<'_, X, XLink, NonNull<X>>| it.map(|x| x.data).collect::<Vec<_>>();
+        let tree_ = tree.as_ref();
+        assert_eq!(data(tree_.range(key(33)..key(60))), [35, 40, 45, 50]);
+        assert_eq!(data(tree_.range(key(35)..=key(60))), [35, 40, 45, 50, 60]);
+        assert_eq!(data(tree_.range(key(66)..)), [70, 80]);
+        assert_eq!(data(tree_.range(..key(30))), [20]);
+        assert_eq!(data(tree_.range::<X, _>(..)).len(), 10);
+        assert_eq!(data(tree_.range(key(41)..key(44))), []);
+        assert_eq!(data(tree_.range(key(60)..key(40))), []);
+        let bounds = (Bound::Excluded(key(40)), Bound::Excluded(key(65)));
+        assert_eq!(data(tree_.range(bounds)), [45, 50, 60]);
+
+        let items: Vec<_> = tree_
+            .range(key(30)..key(65))
+            .rev()
+            .map(|x| x.data)
+            .collect();
+        assert_eq!(items, [60, 50, 45, 40, 35, 30]);
+        let mut it = tree_.range(key(40)..=key(60));
+        assert_eq!(it.next().unwrap().data, 40);
+        assert_eq!(it.next_back().unwrap().data, 60);
+        assert_eq!(it.next().unwrap().data, 45);
+        assert_eq!(it.next_back().unwrap().data, 50);
+        assert!(it.next().is_none());
+        assert!(it.next_back().is_none());
+
+        while X::from(tree.as_mut().pop_front()).is_some() {}
+    }
+
+    #[test]
+    fn test_entry() {
+        fn names(tree: Pin<&BinTree<Z, ZLink>>) -> Vec<&str> {
+            tree.iter().map(|z| z.get_ref().name.as_str()).collect()
+        }
+
+        let mut tree = Box::pin(BinTree::new(ZLink));
+        for name in ["m", "c", "x"] {
+            match tree.as_mut().entry(name) {
+                Entry::Vacant(entry) => assert_eq!(entry.insert(Z::new(name)).name, name),
+                Entry::Occupied(_) => panic!(),
+            }
+        }
+        for name in ["a", "e", "z"] {
+            let Entry::Vacant(entry) = tree.as_mut().entry(name) else {
+                panic!()
+            };
+            entry.insert(Z::new(name));
+        }
+        assert_eq!(names(tree.as_ref()), ["a", "c", "e", "m", "x", "z"]);
+        assert_eq!(tree.as_ref().front().unwrap().name, "a");
+        assert_eq!(tree.as_ref().back().unwrap().name, "z");
+        assert_eq!(tree.as_ref().len(), 6);
+
+        let Entry::Occupied(mut entry) = tree.as_mut().entry("c") else {
+            panic!()
+        };
+        assert_eq!(entry.get().name, "c");
+        assert_eq!(entry.get_mut().name, "c");
+        let item = Z::new("c");
+        let item_ptr = &*item as *const Z;
+        let old = entry.replace(item);
+        assert!(!old.link.is_linked());
+        assert!(ptr::eq(tree.as_ref().get("c").unwrap().get_ref(), item_ptr));
+        for name in ["a", "z"] {
+            let Entry::Occupied(entry) = tree.as_mut().entry(name) else {
+                panic!()
+            };
+            assert!(!entry.replace(Z::new(name)).link.is_linked());
+        }
+        assert_eq!(tree.as_ref().front().unwrap().name, "a");
+        assert_eq!(tree.as_ref().back().unwrap().name, "z");
+        assert_eq!(names(tree.as_ref()), ["a", "c", "e", "m", "x", "z"]);
+
+        let Entry::Occupied(entry) = tree.as_mut().entry("m") else {
+            panic!()
+        };
+        assert_eq!(entry.remove().name, "m");
+        assert!(matches!(tree.as_mut().entry("m"), Entry::Vacant(_)));
+        assert_eq!(
+            tree.as_mut().entry("k").or_insert_with(|| Z::new("k")).name,
+            "k"
+        );
+        assert_eq!(
+            tree.as_mut().entry("k").or_insert_with(|| panic!()).name,
+            "k"
+        );
+        assert_eq!(names(tree.as_ref()), ["a", "c", "e", "k", "x", "z"]);
+        let names_rev: Vec<_> = tree
+            .as_ref()
+            .iter()
+            .rev()
+            .map(|z| z.get_ref().name.as_str())
+            .collect();
+        assert_eq!(names_rev, ["z", "x", "k", "e", "c", "a"]);
+    }
+
+    #[test]
+    fn test_set_algebra() {
+        fn data<'a>(it: impl Iterator<Item = Pin<&'a X>>) -> Vec<i32> {
+            it.map(|x| x.data).collect()
+        }
+
+        let mut a = Box::pin(BinTree::new(XLink));
+        let mut b = Box::pin(BinTree::new(XLink));
+        for i in [5, 2, 8, 1, 3] {
+            a.as_mut().insert(X::new(i));
+        }
+        for i in [4, 8, 2, 9, 3] {
+            b.as_mut().insert(X::new(i));
+        }
+        let (a_, b_) = (a.as_ref(), b.as_ref());
+        assert_eq!(data(a_.union(b_)), [1, 2, 3, 4, 5, 8, 9]);
+        assert!(a_
+            .union(b_)
+            .filter(|x| x.data == 2)
+            .all(|x| ptr::eq(x.get_ref(), a_.get(&key(2)).unwrap().get_ref())));
+        assert_eq!(data(a_.intersection(b_)), [2, 3, 8]);
+        assert_eq!(data(a_.difference(b_)), [1, 5]);
+        assert_eq!(data(b_.difference(a_)), [4, 9]);
+        assert_eq!(data(a_.symmetric_difference(b_)), [1, 4, 5, 9]);
+        assert_eq!(data(a_.intersection(a_)), [1, 2, 3, 5, 8]);
+
+        let mut c = Box::pin(BinTree::new(XLink));
+        a.as_mut().extract_difference(b.as_ref(), c.as_mut());
+        assert_eq!(data(c.as_ref().iter()), [1, 5]);
+        assert_eq!(data(a.as_ref().iter()), [2, 3, 8]);
+        a.as_mut().union_with(b.as_mut());
+        assert_eq!(data(a.as_ref().iter()), [2, 3, 4, 8, 9]);
+        assert_eq!(data(b.as_ref().iter()), [2, 3, 8]);
+        a.as_mut().union_with(c.as_mut());
+        assert_eq!(data(a.as_ref().iter()), [1, 2, 3, 4, 5, 8, 9]);
+        assert!(c.as_ref().is_empty());
+        let mut d = Box::pin(BinTree::new(XLink));
+        a.as_mut().extract_intersection(b.as_ref(), d.as_mut());
+        assert_eq!(data(d.as_ref().iter()), [2, 3, 8]);
+        assert_eq!(data(d.as_ref().iter().rev()), [8, 3, 2]);
+        assert_eq!(data(a.as_ref().iter()), [1, 4, 5, 9]);
+        assert_eq!(data(a.as_ref().iter().rev()), [9, 5, 4, 1]);
+
+        for mut tree in [a, b, c, d] {
+            while X::from(tree.as_mut().pop_front()).is_some() {}
+        }
+    }
+
+    #[test]
+    fn test_extract_balances() {
+        let mut a = Box::pin(BinTree::new(XSizeLink));
+        let mut b = Box::pin(BinTree::new(XSizeLink));
+        let mut c = Box::pin(BinTree::new(XSizeLink));
+        a.as_mut().extend_sorted((0..100).map(|i| Box::new(key(i))));
+        b.as_mut()
+            .extend_sorted((0..100).step_by(3).map(|i| Box::new(key(i))));
+        a.as_mut().extract_difference(b.as_ref(), c.as_mut());
+        check(a.link.top(), None);
+        check(c.link.top(), None);
+        assert_eq!(a.as_ref().len(), 34);
+        assert_eq!(c.as_ref().len(), 66);
+        assert_eq!(height(c.link.top()), 7);
+        let items: Vec<_> = c.as_ref().iter().map(|x| x.data).collect();
+        assert_eq!(items, (0..100).filter(|i| i % 3 != 0).collect::<Vec<_>>());
+        assert_eq!(c.as_ref().front().unwrap().data, 1);
+        assert_eq!(c.as_ref().back().unwrap().data, 98);
+    }
+
+    #[test]
+    #[should_panic(expected = "not empty")]
+    fn test_extract_into_nonempty() {
+        let mut a = Box::pin(BinTree::new(XSizeLink));
+        let b = Box::pin(BinTree::new(XSizeLink));
+        let mut c = Box::pin(BinTree::new(XSizeLink));
+        a.as_mut().insert(Box::new(key(1)));
+        c.as_mut().insert(Box::new(key(2)));
+        a.as_mut().extract_difference(b.as_ref(), c.as_mut());
+    }
+
+    intrusive_adapter! {
+        XSizeLink = X { link: Link<X>, key = data: i32 } with LiterateSize, Box<X>;
+    }
+
+    #[test]
+    fn test_split_append() {
+        fn data(tree: Pin<&BinTree<X, XSizeLink>>) -> Vec<i32> {
+            check(tree.link.top(), None);
+            let items: Vec<_> = tree.iter().map(|x| x.data).collect();
+            let mut rev: Vec<_> = tree.iter().rev().map(|x| x.data).collect();
+            rev.reverse();
+            assert_eq!(items, rev);
+            assert_eq!(tree.front().map(|x| x.data), items.first().copied());
+            assert_eq!(tree.back().map(|x| x.data), items.last().copied());
+            assert_eq!(tree.len(), items.len());
+            items
+        }
+
+        let mut tree = Box::pin(BinTree::new(XSizeLink));
+        for i in [50, 30, 70, 20, 40, 60, 80, 35, 45, 65] {
+            tree.as_mut().insert(Box::new(key(i)));
+        }
+        let new = || Box::pin(BinTree::new(XSizeLink));
+        let mut other = new();
+        tree.as_mut().split_off(&42, other.as_mut());
+        assert_eq!(data(tree.as_ref()), [20, 30, 35, 40]);
+        assert_eq!(data(other.as_ref()), [45, 50, 60, 65, 70, 80]);
+        let mut rest = new();
+        other.as_mut().split_off(&65, rest.as_mut());
+        assert_eq!(data(other.as_ref()), [45, 50, 60]);
+        assert_eq!(data(rest.as_ref()), [65, 70, 80]);
+        let mut empty = new();
+        rest.as_mut().split_off(&90, empty.as_mut());
+        assert_eq!(data(empty.as_ref()), []);
+        let mut all = new();
+        tree.as_mut().split_off(&0, all.as_mut());
+        assert_eq!(data(tree.as_ref()), []);
+
+        rest.as_mut().append(other.as_mut());
+        assert_eq!(data(rest.as_ref()), [45, 50, 60, 65, 70, 80]);
+        assert_eq!(data(other.as_ref()), []);
+        all.as_mut().append(rest.as_mut());
+        assert_eq!(data(all.as_ref()), [20, 30, 35, 40, 45, 50, 60, 65, 70, 80]);
+        tree.as_mut().append(all.as_mut());
+        assert_eq!(data(all.as_ref()), []);
+        assert_eq!(
+            data(tree.as_ref()),
+            [20, 30, 35, 40, 45, 50, 60, 65, 70, 80]
+        );
+        tree.as_mut().append(empty.as_mut());
+        assert_eq!(tree.as_ref().len(), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "not empty")]
+    fn test_split_off_into_nonempty() {
+        let mut tree = Box::pin(BinTree::new(XSizeLink));
+        let mut other = Box::pin(BinTree::new(XSizeLink));
+        tree.as_mut().insert(Box::new(key(1)));
+        other.as_mut().insert(Box::new(key(2)));
+        tree.as_mut().split_off(&0, other.as_mut());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_append_overlap() {
+        let mut a = Box::pin(BinTree::new(XSizeLink));
+        let mut b = Box::pin(BinTree::new(XSizeLink));
+        for i in [1, 3] {
+            a.as_mut().insert(Box::new(key(i)));
+        }
+        b.as_mut().insert(Box::new(key(2)));
+        a.as_mut().append(b.as_mut());
+    }
+
+    fn height(node: Option<NonNull<X>>) -> usize {
+        node.map_or(0, |node| {
+            let link = Link::of::<XLink>(node);
+            1 + height(link.left()).max(height(link.right()))
+        })
+    }
+
+    #[test]
+    fn test_extend_sorted() {
+        for len in [0, 1, 2, 3, 7, 8, 100] {
+            let items = (0..).take_while(|&i| i < len).map(|i| Box::new(key(i)));
+            let mut tree = Box::pin(BinTree::new(XSizeLink));
+            tree.as_mut().extend_sorted(items);
+            check(tree.link.top(), None);
+            let min_height = (len as u32 + 1).next_power_of_two().ilog2();
+            assert_eq!(height(tree.link.top()), min_height as usize);
+            assert_eq!(tree.as_ref().len(), len as usize);
+            let items: Vec<_> = tree.as_ref().iter().map(|x| x.data).collect();
+            assert_eq!(items, (0..len).collect::<Vec<_>>());
+            let items: Vec<_> = tree.as_ref().iter().rev().map(|x| x.data).collect();
+            assert_eq!(items, (0..len).rev().collect::<Vec<_>>());
+            assert_eq!(tree.as_ref().front().map(|x| x.data), (0..len).next());
+            assert_eq!(tree.as_ref().back().map(|x| x.data), (0..len).last());
+        }
+
+        let mut tree = Box::pin(BinTree::new(XSizeLink));
+        tree.as_mut().insert(Box::new(key(5)));
+        tree.as_mut()
+            .extend_sorted((6..13).map(|i| Box::new(key(i))));
+        tree.as_mut().extend_sorted([]);
+        check(tree.link.top(), None);
+        assert_eq!(height(tree.link.top()), 4);
+        let items: Vec<_> = tree.as_ref().iter().map(|x| x.data).collect();
+        assert_eq!(items, (5..13).collect::<Vec<_>>());
+        assert_eq!(tree.as_ref().get(&9).unwrap().data, 9);
+        assert_eq!(tree.as_ref().len(), 8);
+
+        // a full tree extended by as many again ends one level deeper,
+        // not with a second full tree hung below its maximum
+        let mut tree = Box::pin(BinTree::new(XSizeLink));
+        tree.as_mut()
+            .extend_sorted((0..31).map(|i| Box::new(key(i))));
+        assert_eq!(height(tree.link.top()), 5);
+        tree.as_mut()
+            .extend_sorted((31..62).map(|i| Box::new(key(i))));
+        check(tree.link.top(), None);
+        assert_eq!(height(tree.link.top()), 6);
+        let items: Vec<_> = tree.as_ref().iter().map(|x| x.data).collect();
+        assert_eq!(items, (0..62).collect::<Vec<_>>());
+        assert_eq!(tree.as_ref().front().unwrap().data, 0);
+        assert_eq!(tree.as_ref().back().unwrap().data, 61);
+        assert_eq!(tree.as_ref().len(), 62);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "does not match")]
+    fn test_entry_insert_mismatch() {
+        let mut tree = Box::pin(BinTree::new(ZLink));
+        for name in ["c", "m", "x"] {
+            tree.as_mut().insert(Z::new(name));
+        }
+        let Entry::Vacant(entry) = tree.as_mut().entry("e") else {
+            panic!()
+        };
+        entry.insert(Z::new("a"));
+    }
+
+    #[test]
+    #[should_panic(expected = "does not match")]
+    fn test_entry_replace_mismatch() {
+        let mut tree = Box::pin(BinTree::new(ZLink));
+        tree.as_mut().insert(Z::new("c"));
+        let Entry::Occupied(entry) = tree.as_mut().entry("c") else {
+            panic!()
+        };
+        entry.replace(Z::new("d"));
+    }
+
+    #[test]
+    #[should_panic(expected = "already linked")]
+    fn test_insert_twice() {
+        let mut tree = Box::pin(BinTree::new(XLink));
+        let item = X::new(1);
+        tree.as_mut().insert(item);
+        tree.as_mut().insert(item);
+    }
+
+    #[test]
+    fn test_extend_sorted_unsorted() {
+        let mut tree = Box::pin(BinTree::new(XSizeLink));
+        tree.as_mut().insert(Box::new(key(0)));
+        let items = [1, 3, 2, 4].map(|i| Box::new(key(i)));
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            tree.as_mut().extend_sorted(items);
+        }));
+        assert!(result.is_err());
+        check(tree.link.top(), None);
+        let items: Vec<_> = tree.as_ref().iter().map(|x| x.data).collect();
+        assert_eq!(items, [0, 1, 3]);
+        assert_eq!(tree.as_ref().len(), 3);
+
+        let items = [3].map(|i| Box::new(key(i)));
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            tree.as_mut().extend_sorted(items);
+        }));
+        assert!(result.is_err());
+        assert_eq!(tree.as_ref().len(), 3);
+    }
+
+    #[test]
+    fn test_rebalance() {
+        for len in [0, 1, 2, 3, 4, 10, 100] {
+            let mut tree = Box::pin(BinTree::new(XSizeLink));
+            for i in 0..len {
+                tree.as_mut().insert(Box::new(key(i)));
+            }
+            assert_eq!(tree.as_ref().height(), len as usize);
+            let items: Vec<_> = tree
+                .as_ref()
+                .iter()
+                .map(|x| x.get_ref() as *const X)
+                .collect();
+
+            tree.as_mut().rebalance();
+            check(tree.link.top(), None);
+            let min_height = (len as u32 + 1).next_power_of_two().ilog2();
+            assert_eq!(tree.as_ref().height(), min_height as usize);
+            assert_eq!(height(tree.link.top()), min_height as usize);
+            assert_eq!(
+                tree.as_ref()
+                    .iter()
+                    .map(|x| x.get_ref() as *const X)
+                    .collect::<Vec<_>>(),
+                items
+            );
+            assert_eq!(
+                tree.as_ref()
+                    .iter()
+                    .rev()
+                    .map(|x| x.data)
+                    .collect::<Vec<_>>(),
+                (0..len).rev().collect::<Vec<_>>()
+            );
+            assert_eq!(tree.as_ref().front().map(|x| x.data), (0..len).next());
+            assert_eq!(tree.as_ref().back().map(|x| x.data), (0..len).last());
+            for i in 0..len {
+                assert_eq!(tree.as_ref().get(&i).unwrap().data, i);
+            }
+        }
+
+        let mut tree = Box::pin(BinTree::new(XSizeLink));
+        for i in [50, 30, 70, 20, 40, 60, 80, 35, 45, 65, 10, 5, 1] {
+            tree.as_mut().insert(Box::new(key(i)));
+        }
+        assert_eq!(tree.as_ref().height(), 6);
+        tree.as_mut().rebalance();
+        check(tree.link.top(), None);
+        assert_eq!(tree.as_ref().height(), 4);
+        assert_eq!(tree.as_ref().len(), 13);
+    }
 }
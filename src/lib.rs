@@ -8,4 +8,17 @@ pub mod list;
 
 pub mod bintree;
 
-// pub mod avltree;
+pub mod avltree;
+
+pub mod rbtree;
+
+pub mod arena;
+
+pub mod splaytree;
+
+pub mod sgtree;
+
+pub mod treap;
+
+#[cfg(test)]
+mod tree_test;
@@ -1,76 +1,315 @@
-use crate::adapter::{LinkAdapter, Size};
-use crate::ptr::{NonNullPtr, Pointer};
+use crate::adapter::{LinkAdapter, NumerateSize, Size};
+use crate::ptr::{NonNullPtr, OwningPointer, Pointer, UniquePointer};
+use std::cell::Cell;
 use std::cmp;
 use std::fmt;
 use std::fmt::Formatter;
-use std::marker::PhantomData;
+use std::marker::{PhantomData, PhantomPinned};
+use std::mem;
 use std::pin::Pin;
+use std::ptr;
 use std::ptr::NonNull;
 
-pub struct Link<T, P = NonNull<T>> {
-    next_ptr: Option<Pin<NonNullPtr<T, P>>>,
-    prev_ptr: Option<Pin<NonNullPtr<T, P>>>,
+/// Decides what happens to a link that is dropped while still in a list.
+pub trait LinkMode<T, P>: Sized {
+    type Owner: Copy;
+
+    const UNOWNED: Self::Owner;
+
+    /// Records that `link` now belongs to the list headed by `header`.
+    fn attach<A>(header: &Link<T, P, Self>, link: &Link<T, P, Self>)
+    where
+        T: Unpin,
+        P: Pointer<T>,
+        A: LinkAdapter<T, Link = Link<T, P, Self>>;
+
+    fn detach(link: &Link<T, P, Self>);
+}
+
+/// Leaves the list alone; dropping a linked element is a bug.
+pub struct NormalLink;
+
+impl<T, P> LinkMode<T, P> for NormalLink {
+    type Owner = ();
+
+    const UNOWNED: Self::Owner = ();
+
+    fn attach<A>(_: &Link<T, P, Self>, _: &Link<T, P, Self>) {}
+
+    fn detach(_: &Link<T, P, Self>) {}
+}
+
+type Detach<T, P> = fn(NonNull<Link<T, P, AutoUnlink>>, &Link<T, P, AutoUnlink>);
+
+/// Unlinks the element from its list when the link is dropped, and detaches
+/// every element when the list itself is dropped.
+///
+/// Every link remembers the address of its list, so such a list is `!Unpin`.
+/// A drop cannot reach the size of the list, so the adapter must use
+/// `NumerateSize`:
+///
+/// ```compile_fail
+/// use rust_intrusive::adapter::LiterateSize;
+/// use rust_intrusive::intrusive_adapter;
+/// use rust_intrusive::list::{AutoUnlink, DoublyLinkedList, Link};
+/// use std::ptr::NonNull;
+///
+/// struct X {
+///     link: Link<X, NonNull<X>, AutoUnlink>,
+/// }
+///
+/// intrusive_adapter! {
+///     XLink = X { link: Link<X, NonNull<X>, AutoUnlink> } with LiterateSize;
+/// }
+///
+/// let list = DoublyLinkedList::new(XLink);
+/// ```
+pub struct AutoUnlink {
+    _pin: PhantomPinned,
+}
+
+impl AutoUnlink {
+    fn detach<T, P, A>(header: NonNull<Link<T, P, Self>>, link: &Link<T, P, Self>)
+    where
+        T: Unpin,
+        P: Pointer<T>,
+        A: LinkAdapter<T, Link = Link<T, P, Self>>,
+    {
+        if ptr::eq(header.as_ptr(), link) {
+            let mut node = link.next();
+            while let Some(item) = node {
+                let item_link = Link::of::<A>(item);
+                node = item_link.next();
+                item_link.unlink();
+            }
+        } else {
+            let header = unsafe { header.as_ref() };
+            header.set_next::<A>(link.prev(), link.next());
+            header.set_prev::<A>(link.next(), link.prev());
+        }
+        link.unlink();
+    }
+}
+
+impl<T, P> LinkMode<T, P> for AutoUnlink {
+    type Owner = Option<(NonNull<Link<T, P, Self>>, Detach<T, P>)>;
+
+    const UNOWNED: Self::Owner = None;
+
+    fn attach<A>(header: &Link<T, P, Self>, link: &Link<T, P, Self>)
+    where
+        T: Unpin,
+        P: Pointer<T>,
+        A: LinkAdapter<T, Link = Link<T, P, Self>>,
+    {
+        let owner = Some((
+            NonNull::from(header),
+            Self::detach::<T, P, A> as Detach<T, P>,
+        ));
+        header.owner.set(owner);
+        link.owner.set(owner);
+    }
+
+    fn detach(link: &Link<T, P, Self>) {
+        if let Some((header, detach)) = link.owner.get() {
+            detach(header, link);
+        }
+    }
 }
 
-impl<T, P> Link<T, P> {
+#[repr(C)]
+pub struct Link<T, P = NonNull<T>, M = NormalLink>
+where
+    M: LinkMode<T, P>,
+{
+    next_ptr: Cell<Option<Pin<NonNullPtr<T, P>>>>,
+    prev_ptr: Cell<Option<Pin<NonNullPtr<T, P>>>>,
+    owner: Cell<M::Owner>,
+}
+
+impl<T, P, M> Link<T, P, M>
+where
+    M: LinkMode<T, P>,
+{
     pub const fn new() -> Self {
         Self {
-            next_ptr: None,
-            prev_ptr: None,
+            next_ptr: Cell::new(None),
+            prev_ptr: Cell::new(None),
+            owner: Cell::new(M::UNOWNED),
         }
     }
 
+    /// Whether the element has a neighbour in a list, which an element alone
+    /// in its list does not.
     pub const fn is_linked(&self) -> bool {
-        self.next_ptr.is_some() || self.prev_ptr.is_some()
+        unsafe { (*self.next_ptr.as_ptr()).is_some() || (*self.prev_ptr.as_ptr()).is_some() }
+    }
+
+    fn unlink(&self) {
+        self.next_ptr.set(None);
+        self.prev_ptr.set(None);
+        self.owner.set(M::UNOWNED);
+    }
+}
+
+impl<T, P, M> Link<T, P, M>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    M: LinkMode<T, P>,
+{
+    fn of<'a, A>(node: NonNull<T>) -> &'a Self
+    where
+        A: LinkAdapter<T, Link = Link<T, P, M>>,
+    {
+        A::link_ref(unsafe { &*node.as_ptr() })
+    }
+
+    fn next(&self) -> Option<NonNull<T>> {
+        NonNullPtr::to_raw(&self.next_ptr)
+    }
+
+    fn prev(&self) -> Option<NonNull<T>> {
+        NonNullPtr::to_raw(&self.prev_ptr)
+    }
+
+    /// Panics if `item` is already in a list. An element alone in its list
+    /// has no neighbours and looks unlinked, so the front of this list is
+    /// checked too; the lone element of another list still goes unnoticed.
+    fn assert_unlinked<A>(&self, item: NonNull<T>)
+    where
+        A: LinkAdapter<T, Link = Link<T, P, M>>,
+    {
+        assert!(
+            !Self::of::<A>(item).is_linked() && self.next() != Some(item),
+            "the element is already linked"
+        );
+    }
+
+    fn set_next<A>(&self, node: Option<NonNull<T>>, next: Option<NonNull<T>>)
+    where
+        A: LinkAdapter<T, Link = Link<T, P, M>>,
+    {
+        if let Some(node) = node {
+            NonNullPtr::assign_raw(&Self::of::<A>(node).next_ptr, next);
+        } else {
+            NonNullPtr::assign_raw(&self.next_ptr, next);
+        }
+    }
+
+    fn set_prev<A>(&self, node: Option<NonNull<T>>, prev: Option<NonNull<T>>)
+    where
+        A: LinkAdapter<T, Link = Link<T, P, M>>,
+    {
+        if let Some(node) = node {
+            NonNullPtr::assign_raw(&Self::of::<A>(node).prev_ptr, prev);
+        } else {
+            NonNullPtr::assign_raw(&self.prev_ptr, prev);
+        }
     }
 
-    fn unlink(&mut self) {
-        self.next_ptr = None;
-        self.prev_ptr = None;
+    /// Links `item` between `prev` and `next`, where `None` stands for the header.
+    fn link_node<A>(&mut self, prev: Option<NonNull<T>>, item: NonNull<T>, next: Option<NonNull<T>>)
+    where
+        A: LinkAdapter<T, Link = Link<T, P, M>>,
+    {
+        self.assert_unlinked::<A>(item);
+        let item_link = Self::of::<A>(item);
+        M::attach::<A>(self, item_link);
+        NonNullPtr::assign_raw(&item_link.prev_ptr, prev);
+        NonNullPtr::assign_raw(&item_link.next_ptr, next);
+        self.set_next::<A>(prev, Some(item));
+        self.set_prev::<A>(next, Some(item));
+    }
+
+    fn unlink_node<A>(&mut self, node: NonNull<T>)
+    where
+        A: LinkAdapter<T, Link = Link<T, P, M>>,
+    {
+        let node_link = Self::of::<A>(node);
+        let prev = node_link.prev();
+        let next = node_link.next();
+        node_link.unlink();
+        self.set_next::<A>(prev, next);
+        self.set_prev::<A>(next, prev);
     }
 }
 
-impl<T, P> Default for Link<T, P> {
+impl<T, P, M> Default for Link<T, P, M>
+where
+    M: LinkMode<T, P>,
+{
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T, P> Unpin for Link<T, P> where T: Unpin {}
+/// The `Size`s that a list whose links use the mode `M` can keep.
+pub trait SizeFor<M>: Size {}
+
+impl<S> SizeFor<NormalLink> for S where S: Size {}
+
+impl SizeFor<AutoUnlink> for NumerateSize {}
+
+impl<T, P, M> Unpin for Link<T, P, M>
+where
+    T: Unpin,
+    M: LinkMode<T, P>,
+{
+}
 
-impl<T, P> cmp::PartialEq for Link<T, P> {
+impl<T, P, M> cmp::PartialEq for Link<T, P, M>
+where
+    M: LinkMode<T, P>,
+{
     fn eq(&self, _: &Self) -> bool {
         true
     }
 }
 
-impl<T, P> cmp::Eq for Link<T, P> {}
+impl<T, P, M> cmp::Eq for Link<T, P, M> where M: LinkMode<T, P> {}
 
-impl<T, P> cmp::PartialOrd for Link<T, P> {
+impl<T, P, M> cmp::PartialOrd for Link<T, P, M>
+where
+    M: LinkMode<T, P>,
+{
     fn partial_cmp(&self, _: &Self) -> Option<cmp::Ordering> {
         Some(cmp::Ordering::Equal)
     }
 }
 
-impl<T, P> cmp::Ord for Link<T, P> {
+impl<T, P, M> cmp::Ord for Link<T, P, M>
+where
+    M: LinkMode<T, P>,
+{
     fn cmp(&self, _: &Self) -> cmp::Ordering {
         cmp::Ordering::Equal
     }
 }
 
-impl<T, P> fmt::Debug for Link<T, P>
+impl<T, P, M> Drop for Link<T, P, M>
+where
+    M: LinkMode<T, P>,
+{
+    fn drop(&mut self) {
+        M::detach(self);
+    }
+}
+
+impl<T, P, M> fmt::Debug for Link<T, P, M>
 where
     P: fmt::Debug,
+    M: LinkMode<T, P>,
 {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "{{ prev: ")?;
-        if let Some(prev) = &self.prev_ptr {
+        if let Some(prev) = unsafe { &*self.prev_ptr.as_ptr() } {
             write!(f, "{:?}", prev)?;
         } else {
             write!(f, "0x0")?;
         }
         write!(f, ", next: ")?;
-        if let Some(next) = &self.next_ptr {
+        if let Some(next) = unsafe { &*self.next_ptr.as_ptr() } {
             write!(f, "{:?}", next)?;
         } else {
             write!(f, "0x0")?;
@@ -79,133 +318,333 @@ where
     }
 }
 
-pub struct Iter<'a, T, A, P> {
-    link: *const Link<T, P>,
+pub struct Iter<'a, T, A, P, M = NormalLink>
+where
+    M: LinkMode<T, P>,
+{
+    link: *const Link<T, P, M>,
     _marker: PhantomData<&'a A>,
 }
 
-impl<'a, T, A, P> Iterator for Iter<'a, T, A, P>
+impl<'a, T, A, P, M> Iterator for Iter<'a, T, A, P, M>
 where
     T: Unpin + 'a,
     P: Pointer<T> + 'a,
-    A: LinkAdapter<T, Link = Link<T, P>>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
 {
     type Item = Pin<&'a T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let link = unsafe { &*self.link };
-        if let Some(item) = &link.next_ptr {
-            self.link = A::link_ref(item);
-            Some(item.as_ref())
-        } else {
-            None
-        }
+        let item = unsafe { &*self.link }.next()?;
+        self.link = Link::of::<A>(item);
+        Some(Pin::new(unsafe { &*item.as_ptr() }))
     }
 }
 
-impl<'a, T, A, P> DoubleEndedIterator for Iter<'a, T, A, P>
+impl<'a, T, A, P, M> DoubleEndedIterator for Iter<'a, T, A, P, M>
 where
     T: Unpin + 'a,
     P: Pointer<T> + 'a,
-    A: LinkAdapter<T, Link = Link<T, P>>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
 {
     fn next_back(&mut self) -> Option<Self::Item> {
-        let link = unsafe { &*self.link };
-        if let Some(item) = &link.prev_ptr {
-            self.link = A::link_ref(item);
-            Some(item.as_ref())
-        } else {
-            None
-        }
+        let item = unsafe { &*self.link }.prev()?;
+        self.link = Link::of::<A>(item);
+        Some(Pin::new(unsafe { &*item.as_ptr() }))
     }
 }
 
-pub struct IterMut<'a, T, A, P> {
-    link: *mut Link<T, P>,
+pub struct IterMut<'a, T, A, P, M = NormalLink>
+where
+    M: LinkMode<T, P>,
+{
+    link: *const Link<T, P, M>,
     _marker: PhantomData<&'a A>,
 }
 
-impl<'a, T, A, P> Iterator for IterMut<'a, T, A, P>
+impl<'a, T, A, P, M> Iterator for IterMut<'a, T, A, P, M>
 where
     T: Unpin + 'a,
     P: Pointer<T> + 'a,
-    A: LinkAdapter<T, Link = Link<T, P>>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
 {
     type Item = Pin<&'a mut T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let link = unsafe { &mut *self.link };
-        if let Some(item) = &mut link.next_ptr {
-            self.link = A::link_mut(item);
-            Some(item.as_mut())
-        } else {
-            None
-        }
+        let item = unsafe { &*self.link }.next()?;
+        self.link = Link::of::<A>(item);
+        Some(Pin::new(unsafe { &mut *item.as_ptr() }))
     }
 }
 
-impl<'a, T, A, P> DoubleEndedIterator for IterMut<'a, T, A, P>
+impl<'a, T, A, P, M> DoubleEndedIterator for IterMut<'a, T, A, P, M>
 where
     T: Unpin + 'a,
     P: Pointer<T> + 'a,
-    A: LinkAdapter<T, Link = Link<T, P>>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
 {
     fn next_back(&mut self) -> Option<Self::Item> {
-        let link = unsafe { &mut *self.link };
-        if let Some(item) = &mut link.prev_ptr {
-            self.link = A::link_mut(item);
-            Some(item.as_mut())
-        } else {
-            None
-        }
+        let item = unsafe { &*self.link }.prev()?;
+        self.link = Link::of::<A>(item);
+        Some(Pin::new(unsafe { &mut *item.as_ptr() }))
     }
 }
 
-pub struct IntoIter<'a, T, A, P>
+pub struct IntoIter<'a, T, A, P, M = NormalLink>
 where
-    A: LinkAdapter<T>,
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
 {
-    item: Pin<&'a mut DoublyLinkedList<T, A, P>>,
+    item: Pin<&'a mut DoublyLinkedList<T, A, P, M>>,
 }
 
-impl<'a, T, A, P> Iterator for IntoIter<'a, T, A, P>
+impl<'a, T, A, P, M> Iterator for IntoIter<'a, T, A, P, M>
 where
     T: Unpin,
     P: Pointer<T>,
-    A: LinkAdapter<T, Link = Link<T, P>>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
 {
-    type Item = NonNull<T>;
+    type Item = A::Pointer;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.item.as_mut().pop_front()
     }
 }
 
-impl<'a, T, A, P> DoubleEndedIterator for IntoIter<'a, T, A, P>
+impl<'a, T, A, P, M> DoubleEndedIterator for IntoIter<'a, T, A, P, M>
 where
     T: Unpin,
     P: Pointer<T>,
-    A: LinkAdapter<T, Link = Link<T, P>>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
 {
     fn next_back(&mut self) -> Option<Self::Item> {
         self.item.as_mut().pop_back()
     }
 }
 
+/// A read-only position in a list; `None` is the ghost position between the back and the front.
+pub struct Cursor<'a, T, A, P, M = NormalLink>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
+{
+    current: Option<NonNull<T>>,
+    list: Pin<&'a DoublyLinkedList<T, A, P, M>>,
+}
+
+impl<'a, T, A, P, M> Cursor<'a, T, A, P, M>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
+{
+    pub fn move_next(&mut self) {
+        self.current = match self.current {
+            Some(node) => Link::of::<A>(node).next(),
+            None => self.list.link.next(),
+        };
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = match self.current {
+            Some(node) => Link::of::<A>(node).prev(),
+            None => self.list.link.prev(),
+        };
+    }
+
+    pub fn current(&self) -> Option<Pin<&'a T>> {
+        self.current
+            .map(|node| Pin::new(unsafe { &*node.as_ptr() }))
+    }
+
+    pub fn peek_next(&self) -> Option<Pin<&'a T>> {
+        let next = match self.current {
+            Some(node) => Link::of::<A>(node).next(),
+            None => self.list.link.next(),
+        };
+        next.map(|node| Pin::new(unsafe { &*node.as_ptr() }))
+    }
+
+    pub fn peek_prev(&self) -> Option<Pin<&'a T>> {
+        let prev = match self.current {
+            Some(node) => Link::of::<A>(node).prev(),
+            None => self.list.link.prev(),
+        };
+        prev.map(|node| Pin::new(unsafe { &*node.as_ptr() }))
+    }
+}
+
+impl<'a, T, A, P, M> Clone for Cursor<'a, T, A, P, M>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            current: self.current,
+            list: self.list,
+        }
+    }
+}
+
+/// A position in a list that can edit the list around it.
+pub struct CursorMut<'a, T, A, P, M = NormalLink>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
+{
+    current: Option<NonNull<T>>,
+    list: Pin<&'a mut DoublyLinkedList<T, A, P, M>>,
+}
+
+impl<'a, T, A, P, M> CursorMut<'a, T, A, P, M>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
+{
+    pub fn move_next(&mut self) {
+        self.current = match self.current {
+            Some(node) => Link::of::<A>(node).next(),
+            None => self.list.link.next(),
+        };
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = match self.current {
+            Some(node) => Link::of::<A>(node).prev(),
+            None => self.list.link.prev(),
+        };
+    }
+
+    pub fn current(&mut self) -> Option<Pin<&mut T>>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        self.current
+            .map(|node| Pin::new(unsafe { &mut *node.as_ptr() }))
+    }
+
+    pub fn peek_next(&mut self) -> Option<Pin<&mut T>>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        let next = match self.current {
+            Some(node) => Link::of::<A>(node).next(),
+            None => self.list.link.next(),
+        };
+        next.map(|node| Pin::new(unsafe { &mut *node.as_ptr() }))
+    }
+
+    pub fn peek_prev(&mut self) -> Option<Pin<&mut T>>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        let prev = match self.current {
+            Some(node) => Link::of::<A>(node).prev(),
+            None => self.list.link.prev(),
+        };
+        prev.map(|node| Pin::new(unsafe { &mut *node.as_ptr() }))
+    }
+
+    pub fn as_cursor(&self) -> Cursor<'_, T, A, P, M> {
+        Cursor {
+            current: self.current,
+            list: self.list.as_ref(),
+        }
+    }
+
+    /// Inserts `item` before the current element, or at the back on the ghost position.
+    pub fn insert_before(&mut self, item: A::Pointer) {
+        let item = item.into_raw();
+        let list = unsafe { self.list.as_mut().get_unchecked_mut() };
+        let prev = match self.current {
+            Some(node) => Link::of::<A>(node).prev(),
+            None => list.link.prev(),
+        };
+        list.link.link_node::<A>(prev, item, self.current);
+        list.size.incr();
+    }
+
+    /// Inserts `item` after the current element, or at the front on the ghost position.
+    pub fn insert_after(&mut self, item: A::Pointer) {
+        let item = item.into_raw();
+        let list = unsafe { self.list.as_mut().get_unchecked_mut() };
+        let next = match self.current {
+            Some(node) => Link::of::<A>(node).next(),
+            None => list.link.next(),
+        };
+        list.link.link_node::<A>(self.current, item, next);
+        list.size.incr();
+    }
+
+    /// Unlinks the current element and moves to the next one.
+    pub fn remove_current(&mut self) -> Option<A::Pointer> {
+        let node = self.current?;
+        let list = unsafe { self.list.as_mut().get_unchecked_mut() };
+        self.current = Link::of::<A>(node).next();
+        list.link.unlink_node::<A>(node);
+        list.size.decr();
+        Some(unsafe { A::Pointer::from_raw(node) })
+    }
+
+    /// Puts `item` in the place of the current element and returns the latter,
+    /// or hands `item` back on the ghost position.
+    pub fn replace_current(&mut self, item: A::Pointer) -> Result<A::Pointer, A::Pointer> {
+        let Some(node) = self.current else {
+            return Err(item);
+        };
+        let item = item.into_raw();
+        let list = unsafe { self.list.as_mut().get_unchecked_mut() };
+        let node_link = Link::of::<A>(node);
+        let prev = node_link.prev();
+        let next = node_link.next();
+        list.link.unlink_node::<A>(node);
+        list.link.link_node::<A>(prev, item, next);
+        self.current = Some(item);
+        Ok(unsafe { A::Pointer::from_raw(node) })
+    }
+}
+
 #[derive(Debug)]
-pub struct DoublyLinkedList<T, A, P>
+pub struct DoublyLinkedList<T, A, P, M = NormalLink>
 where
-    A: LinkAdapter<T>,
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
 {
     size: A::Size,
-    link: Link<T, P>,
+    link: Link<T, P, M>,
 }
 
-impl<T, A, P> DoublyLinkedList<T, A, P>
+impl<T, A, P, M> DoublyLinkedList<T, A, P, M>
 where
-    A: LinkAdapter<T>,
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
 {
-    pub fn new(_: A) -> Self {
+    pub fn new(_: A) -> Self
+    where
+        A::Size: SizeFor<M>,
+    {
         Self {
             size: Default::default(),
             link: Link::new(),
@@ -213,153 +652,184 @@ where
     }
 }
 
-impl<T, A, P> DoublyLinkedList<T, A, P>
+impl<T, A, P, M> DoublyLinkedList<T, A, P, M>
 where
     T: Unpin,
-    A: LinkAdapter<T>,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
 {
 }
 
-impl<T, A, P> DoublyLinkedList<T, A, P>
+impl<T, A, P, M> DoublyLinkedList<T, A, P, M>
 where
     T: Unpin,
     P: Pointer<T>,
-    A: LinkAdapter<T, Link = Link<T, P>>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
 {
-    pub fn push_front(self: Pin<&mut Self>, mut item: NonNull<T>) {
-        let item_link = A::link_mut(unsafe { item.as_mut() });
-        debug_assert_eq!(item_link.is_linked(), false);
-
-        let self_ = Pin::into_inner(self);
-        let head_ptr = &mut self_.link.next_ptr;
-        let tail_ptr = &mut self_.link.prev_ptr;
-        if let Some(head) = head_ptr {
-            let head_link = A::link_mut(head.as_mut().get_mut());
-            NonNullPtr::assign(&mut head_link.prev_ptr, item);
-            NonNullPtr::assign_pin(&mut item_link.next_ptr, head);
+    pub fn push_front(self: Pin<&mut Self>, item: A::Pointer) {
+        let item = item.into_raw();
+        self.link.assert_unlinked::<A>(item);
+        let item_link = Link::of::<A>(item);
+
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        M::attach::<A>(&self_.link, item_link);
+        if let Some(head) = self_.link.next() {
+            NonNullPtr::assign(&Link::of::<A>(head).prev_ptr, item);
+            NonNullPtr::assign(&item_link.next_ptr, head);
         } else {
-            NonNullPtr::assign(tail_ptr, item);
+            NonNullPtr::assign(&self_.link.prev_ptr, item);
         }
-        NonNullPtr::assign(head_ptr, item);
+        NonNullPtr::assign(&self_.link.next_ptr, item);
         self_.size.incr();
     }
 
-    pub fn push_back(self: Pin<&mut Self>, mut item: NonNull<T>) {
-        let item_link = A::link_mut(unsafe { item.as_mut() });
-        debug_assert_eq!(item_link.is_linked(), false);
+    pub fn push_back(self: Pin<&mut Self>, item: A::Pointer) {
+        let item = item.into_raw();
+        self.link.assert_unlinked::<A>(item);
+        let item_link = Link::of::<A>(item);
 
-        let self_ = Pin::into_inner(self);
-        let head_ptr = &mut self_.link.next_ptr;
-        let tail_ptr = &mut self_.link.prev_ptr;
-        if let Some(tail) = tail_ptr {
-            let tail_link = A::link_mut(tail.as_mut().get_mut());
-            NonNullPtr::assign(&mut tail_link.next_ptr, item);
-            NonNullPtr::assign_pin(&mut item_link.prev_ptr, tail);
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        M::attach::<A>(&self_.link, item_link);
+        if let Some(tail) = self_.link.prev() {
+            NonNullPtr::assign(&Link::of::<A>(tail).next_ptr, item);
+            NonNullPtr::assign(&item_link.prev_ptr, tail);
         } else {
-            NonNullPtr::assign(head_ptr, item);
+            NonNullPtr::assign(&self_.link.next_ptr, item);
         }
-        NonNullPtr::assign(tail_ptr, item);
+        NonNullPtr::assign(&self_.link.prev_ptr, item);
         self_.size.incr();
     }
 
-    pub fn pop_front(self: Pin<&mut Self>) -> Option<NonNull<T>> {
-        let self_ = Pin::into_inner(self);
-        let head_ptr = &mut self_.link.next_ptr;
-        if let Some(head) = head_ptr {
-            let mut head = NonNull::from(head.as_mut().get_mut());
-            let head_link = A::link_mut(unsafe { head.as_mut() });
-            if let Some(next) = &mut head_link.next_ptr {
-                let next_link = A::link_mut(next.as_mut().get_mut());
-                NonNullPtr::assign_ptr(&mut next_link.prev_ptr, &mut head_link.prev_ptr);
-                NonNullPtr::assign_pin(head_ptr, next);
-            } else {
-                self_.link.unlink();
-            }
-            head_link.unlink();
-            self_.size.decr();
-            Some(head)
+    pub fn pop_front(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let head = self_.link.next()?;
+        let head_link = Link::of::<A>(head);
+        if let Some(next) = head_link.next() {
+            NonNullPtr::assign_raw(&Link::of::<A>(next).prev_ptr, head_link.prev());
+            NonNullPtr::assign(&self_.link.next_ptr, next);
         } else {
-            None
+            self_.link.unlink();
         }
+        head_link.unlink();
+        self_.size.decr();
+        Some(unsafe { A::Pointer::from_raw(head) })
     }
 
-    pub fn pop_back(self: Pin<&mut Self>) -> Option<NonNull<T>> {
-        let self_ = Pin::into_inner(self);
-        let tail_ptr = &mut self_.link.prev_ptr;
-        if let Some(tail) = tail_ptr {
-            let mut tail = NonNull::from(tail.as_mut().get_mut());
-            let tail_link = A::link_mut(unsafe { tail.as_mut() });
-            if let Some(prev) = &mut tail_link.prev_ptr {
-                let prev_link = A::link_mut(prev.as_mut().get_mut());
-                NonNullPtr::assign_ptr(&mut prev_link.next_ptr, &mut tail_link.next_ptr);
-                NonNullPtr::assign_pin(tail_ptr, prev);
-            } else {
-                self_.link.unlink();
-            }
-            tail_link.unlink();
-            self_.size.decr();
-            Some(tail)
+    pub fn pop_back(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let tail = self_.link.prev()?;
+        let tail_link = Link::of::<A>(tail);
+        if let Some(prev) = tail_link.prev() {
+            NonNullPtr::assign_raw(&Link::of::<A>(prev).next_ptr, tail_link.next());
+            NonNullPtr::assign(&self_.link.prev_ptr, prev);
         } else {
-            None
+            self_.link.unlink();
         }
+        tail_link.unlink();
+        self_.size.decr();
+        Some(unsafe { A::Pointer::from_raw(tail) })
+    }
+
+    /// Unlinks `item` from the list in constant time.
+    ///
+    /// # Safety
+    ///
+    /// `item` must be linked into this very list.
+    pub unsafe fn remove(self: Pin<&mut Self>, item: NonNull<T>) -> A::Pointer {
+        debug_assert!(self
+            .as_ref()
+            .iter()
+            .any(|x| ptr::eq(x.get_ref(), item.as_ptr())));
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        self_.link.unlink_node::<A>(item);
+        self_.size.decr();
+        A::Pointer::from_raw(item)
     }
 
     pub fn front(self: Pin<&Self>) -> Option<Pin<&T>> {
-        let self_ = Pin::into_inner(self);
-        if let Some(first) = &self_.link.next_ptr {
-            Some(first.as_ref())
-        } else {
-            None
-        }
+        let first = Pin::get_ref(self).link.next()?;
+        Some(Pin::new(unsafe { &*first.as_ptr() }))
     }
 
-    pub fn front_mut(self: Pin<&mut Self>) -> Option<Pin<&mut T>> {
-        let self_ = Pin::into_inner(self);
-        if let Some(first) = &mut self_.link.next_ptr {
-            Some(first.as_mut())
-        } else {
-            None
-        }
+    pub fn front_mut(self: Pin<&mut Self>) -> Option<Pin<&mut T>>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        let first = self.link.next()?;
+        Some(Pin::new(unsafe { &mut *first.as_ptr() }))
     }
 
     pub fn back(self: Pin<&Self>) -> Option<Pin<&T>> {
-        let self_ = Pin::into_inner(self);
-        if let Some(last) = &self_.link.prev_ptr {
-            Some(last.as_ref())
-        } else {
-            None
-        }
+        let last = Pin::get_ref(self).link.prev()?;
+        Some(Pin::new(unsafe { &*last.as_ptr() }))
     }
 
-    pub fn back_mut(self: Pin<&mut Self>) -> Option<Pin<&mut T>> {
-        let self_ = Pin::into_inner(self);
-        if let Some(last) = &mut self_.link.prev_ptr {
-            Some(last.as_mut())
-        } else {
-            None
-        }
+    pub fn back_mut(self: Pin<&mut Self>) -> Option<Pin<&mut T>>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        let last = self.link.prev()?;
+        Some(Pin::new(unsafe { &mut *last.as_ptr() }))
     }
 
-    pub const fn iter(self: Pin<&Self>) -> Iter<T, A, P> {
-        let self_ = Pin::into_inner(self);
+    pub const fn iter(self: Pin<&Self>) -> Iter<'_, T, A, P, M> {
+        let self_ = Pin::get_ref(self);
         Iter {
             link: &self_.link,
             _marker: PhantomData,
         }
     }
 
-    pub const fn iter_mut(self: Pin<&mut Self>) -> IterMut<T, A, P> {
-        let self_ = Pin::into_inner(self);
+    pub const fn iter_mut(self: Pin<&mut Self>) -> IterMut<'_, T, A, P, M>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
         IterMut {
-            link: &mut self_.link,
+            link: &self_.link,
             _marker: PhantomData,
         }
     }
 
-    pub fn into_iter(self: Pin<&mut Self>) -> IntoIter<T, A, P> {
+    pub fn into_iter(self: Pin<&mut Self>) -> IntoIter<'_, T, A, P, M> {
         IntoIter { item: self }
     }
 
+    pub fn cursor_front(self: Pin<&Self>) -> Cursor<'_, T, A, P, M> {
+        Cursor {
+            current: self.link.next(),
+            list: self,
+        }
+    }
+
+    pub fn cursor_back(self: Pin<&Self>) -> Cursor<'_, T, A, P, M> {
+        Cursor {
+            current: self.link.prev(),
+            list: self,
+        }
+    }
+
+    pub fn cursor_front_mut(self: Pin<&mut Self>) -> CursorMut<'_, T, A, P, M> {
+        CursorMut {
+            current: self.link.next(),
+            list: self,
+        }
+    }
+
+    pub fn cursor_back_mut(self: Pin<&mut Self>) -> CursorMut<'_, T, A, P, M> {
+        CursorMut {
+            current: self.link.prev(),
+            list: self,
+        }
+    }
+
+    /// Unlinks every element, releasing the owned ones.
+    pub fn clear(mut self: Pin<&mut Self>) {
+        while self.as_mut().pop_front().is_some() {}
+    }
+
     pub fn is_empty(self: Pin<&Self>) -> bool {
         self.size.is_empty(self.iter())
     }
@@ -381,19 +851,39 @@ where
     }
 }
 
-impl<T, A, P> Default for DoublyLinkedList<T, A, P>
+impl<T, A, P, M> Default for DoublyLinkedList<T, A, P, M>
 where
-    A: LinkAdapter<T> + Default,
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P, M>> + Default,
+    A::Size: SizeFor<M>,
+    M: LinkMode<T, P>,
 {
     fn default() -> Self {
         Self::new(A::default())
     }
 }
 
-impl<T, A, P> Unpin for DoublyLinkedList<T, A, P>
+impl<T, A, P, M> Drop for DoublyLinkedList<T, A, P, M>
 where
     T: Unpin,
-    A: LinkAdapter<T>,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P>,
+{
+    fn drop(&mut self) {
+        if mem::needs_drop::<A::Pointer>() {
+            unsafe { Pin::new_unchecked(self) }.clear();
+        }
+    }
+}
+
+impl<T, A, P, M> Unpin for DoublyLinkedList<T, A, P, M>
+where
+    T: Unpin,
+    P: Pointer<T> + Unpin,
+    A: LinkAdapter<T, Link = Link<T, P, M>>,
+    M: LinkMode<T, P> + Unpin,
 {
 }
 
@@ -401,6 +891,7 @@ where
 mod test {
     use super::*;
     use crate::adapter::NumerateSize;
+    use std::rc::Rc;
 
     #[derive(PartialEq, Eq, PartialOrd, Ord)]
     struct X {
@@ -445,6 +936,7 @@ mod test {
     impl LinkAdapter<X> for XLink {
         type Link = Link<X>;
         type Size = NumerateSize;
+        type Pointer = NonNull<X>;
 
         fn link_ref(data: &X) -> &Self::Link {
             &data.link
@@ -597,4 +1089,327 @@ mod test {
         assert_eq!(lst.as_ref().is_empty(), true);
         assert_eq!(lst.as_mut().pop_front(), None);
     }
+
+    fn collect(lst: Pin<&DoublyLinkedList<X, XLink, NonNull<X>>>) -> Vec<i32> {
+        let items: Vec<_> = lst.iter().map(|x| x.data).collect();
+        let mut rev: Vec<_> = lst.iter().rev().map(|x| x.data).collect();
+        rev.reverse();
+        assert_eq!(items, rev);
+        assert_eq!(items.len(), lst.len());
+        items
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut lst = Box::pin(DoublyLinkedList::new(XLink));
+        for i in 1..=3 {
+            lst.as_mut().push_back(X::new(i));
+        }
+        // [1,2,3]
+        let mut cur = lst.as_ref().cursor_front();
+        assert_eq!(cur.current().unwrap().data, 1);
+        assert_eq!(cur.peek_prev(), None);
+        assert_eq!(cur.peek_next().unwrap().data, 2);
+        cur.move_next();
+        cur.move_next();
+        assert_eq!(cur.current().unwrap().data, 3);
+        cur.move_next();
+        assert_eq!(cur.current(), None);
+        assert_eq!(cur.peek_next().unwrap().data, 1);
+        assert_eq!(cur.peek_prev().unwrap().data, 3);
+        cur.move_prev();
+        assert_eq!(cur.current().unwrap().data, 3);
+
+        let mut cur = lst.as_ref().cursor_back();
+        assert_eq!(cur.current().unwrap().data, 3);
+        cur.move_prev();
+        cur.move_prev();
+        cur.move_prev();
+        assert_eq!(cur.current(), None);
+        cur.move_prev();
+        assert_eq!(cur.current().unwrap().data, 3);
+
+        while X::from(lst.as_mut().pop_front()).is_some() {}
+        let mut cur = lst.as_ref().cursor_front();
+        assert_eq!(cur.current(), None);
+        cur.move_next();
+        assert_eq!(cur.current(), None);
+        assert_eq!(cur.peek_prev(), None);
+    }
+
+    #[test]
+    fn test_cursor_mut() {
+        let mut lst = Box::pin(DoublyLinkedList::new(XLink));
+        let mut cur = lst.as_mut().cursor_front_mut();
+        assert_eq!(cur.remove_current(), None);
+        cur.insert_before(X::new(2));
+        cur.insert_after(X::new(1));
+        cur.insert_before(X::new(3));
+        assert_eq!(collect(lst.as_ref()), [1, 2, 3]);
+
+        let mut cur = lst.as_mut().cursor_front_mut();
+        cur.move_next();
+        // [1,(2),3]
+        cur.insert_before(X::new(4));
+        cur.insert_after(X::new(5));
+        assert_eq!(cur.peek_prev().unwrap().data, 4);
+        assert_eq!(cur.peek_next().unwrap().data, 5);
+        cur.current().unwrap().data = 6;
+        assert_eq!(cur.as_cursor().current().unwrap().data, 6);
+        assert_eq!(collect(lst.as_ref()), [1, 4, 6, 5, 3]);
+
+        let mut cur = lst.as_mut().cursor_front_mut();
+        let item = X::from(cur.remove_current()).unwrap();
+        assert_eq!(item.data, 1);
+        assert_eq!(cur.current().unwrap().data, 4);
+        let item = X::from(cur.replace_current(X::new(7)).ok()).unwrap();
+        assert_eq!(item.data, 4);
+        assert_eq!(cur.current().unwrap().data, 7);
+        assert_eq!(collect(lst.as_ref()), [7, 6, 5, 3]);
+
+        let mut cur = lst.as_mut().cursor_back_mut();
+        let item = X::from(cur.replace_current(X::new(8)).ok()).unwrap();
+        assert_eq!(item.data, 3);
+        let item = X::from(cur.remove_current()).unwrap();
+        assert_eq!(item.data, 8);
+        assert_eq!(cur.current(), None);
+        let item = X::new(9);
+        assert_eq!(cur.replace_current(item), Err(item));
+        X::from(Some(item));
+        assert_eq!(lst.as_ref().back().unwrap().data, 5);
+        assert_eq!(collect(lst.as_ref()), [7, 6, 5]);
+
+        let mut cur = lst.as_mut().cursor_front_mut();
+        while X::from(cur.remove_current()).is_some() {}
+        assert!(lst.as_ref().is_empty());
+        assert_eq!(lst.as_ref().front(), None);
+        assert_eq!(lst.as_ref().back(), None);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut lst = Box::pin(DoublyLinkedList::new(XLink));
+        let items: Vec<_> = (1..=5).map(X::new).collect();
+        for &item in &items {
+            lst.as_mut().push_back(item);
+        }
+        // [1,2,3,4,5]
+        unsafe { lst.as_mut().remove(items[2]) };
+        assert_eq!(X::from(Some(items[2])).unwrap().data, 3);
+        assert_eq!(collect(lst.as_ref()), [1, 2, 4, 5]);
+
+        unsafe { lst.as_mut().remove(items[0]) };
+        assert_eq!(X::from(Some(items[0])).unwrap().data, 1);
+        assert_eq!(collect(lst.as_ref()), [2, 4, 5]);
+        assert_eq!(lst.as_ref().front().unwrap().data, 2);
+
+        unsafe { lst.as_mut().remove(items[4]) };
+        assert_eq!(X::from(Some(items[4])).unwrap().data, 5);
+        assert_eq!(collect(lst.as_ref()), [2, 4]);
+        assert_eq!(lst.as_ref().back().unwrap().data, 4);
+
+        unsafe { lst.as_mut().remove(items[1]) };
+        unsafe { lst.as_mut().remove(items[3]) };
+        X::from(Some(items[1]));
+        X::from(Some(items[3]));
+        assert!(lst.as_ref().is_empty());
+        assert_eq!(lst.as_ref().front(), None);
+        assert_eq!(lst.as_ref().back(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "already linked")]
+    fn test_push_twice() {
+        let mut lst = Box::pin(DoublyLinkedList::new(XLink));
+        let item = X::new(1);
+        lst.as_mut().push_back(item);
+        lst.as_mut().push_front(item);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn test_remove_foreign() {
+        let mut lst = Box::pin(DoublyLinkedList::new(XLink));
+        let mut other = Box::pin(DoublyLinkedList::new(XLink));
+        lst.as_mut().push_back(X::new(1));
+        let item = X::new(2);
+        other.as_mut().push_back(item);
+        unsafe { lst.as_mut().remove(item) };
+    }
+
+    struct Y {
+        data: i32,
+        link: Link<Self, NonNull<Self>, AutoUnlink>,
+    }
+
+    impl Y {
+        fn new(data: i32) -> Box<Self> {
+            Box::new(Y {
+                data,
+                link: Link::new(),
+            })
+        }
+    }
+
+    struct YLink;
+
+    impl LinkAdapter<Y> for YLink {
+        type Link = Link<Y, NonNull<Y>, AutoUnlink>;
+        type Size = NumerateSize;
+        type Pointer = NonNull<Y>;
+
+        fn link_ref(data: &Y) -> &Self::Link {
+            &data.link
+        }
+
+        fn link_mut(data: &mut Y) -> &mut Self::Link {
+            &mut data.link
+        }
+    }
+
+    #[test]
+    fn test_auto_unlink() {
+        let mut lst = Box::pin(DoublyLinkedList::new(YLink));
+        let mut items: Vec<_> = (1..=5).map(Y::new).collect();
+        for item in &mut items {
+            lst.as_mut().push_back(NonNull::from(item.as_mut()));
+        }
+        let data = |lst: Pin<&DoublyLinkedList<Y, YLink, NonNull<Y>, _>>| {
+            lst.iter().map(|x| x.data).collect::<Vec<_>>()
+        };
+        // [1,2,3,4,5]
+        drop(items.remove(2));
+        assert_eq!(data(lst.as_ref()), [1, 2, 4, 5]);
+        drop(items.remove(0));
+        assert_eq!(data(lst.as_ref()), [2, 4, 5]);
+        assert_eq!(lst.as_ref().front().unwrap().data, 2);
+        drop(items.pop());
+        assert_eq!(data(lst.as_ref()), [2, 4]);
+        assert_eq!(lst.as_ref().back().unwrap().data, 4);
+        assert_eq!(
+            lst.as_ref()
+                .iter()
+                .rev()
+                .map(|x| x.data)
+                .collect::<Vec<_>>(),
+            [4, 2]
+        );
+
+        let popped = lst.as_mut().pop_front().unwrap();
+        assert_eq!(unsafe { popped.as_ref() }.data, 2);
+        assert!(unsafe { popped.as_ref() }.link.owner.get().is_none());
+        lst.as_mut().push_front(popped);
+
+        drop(lst);
+        for item in &items {
+            assert!(!item.link.is_linked());
+            assert!(item.link.owner.get().is_none());
+        }
+    }
+
+    #[derive(Debug)]
+    struct XBoxLink;
+
+    impl LinkAdapter<X> for XBoxLink {
+        type Link = Link<X>;
+        type Size = NumerateSize;
+        type Pointer = Box<X>;
+
+        fn link_ref(data: &X) -> &Self::Link {
+            &data.link
+        }
+
+        fn link_mut(data: &mut X) -> &mut Self::Link {
+            &mut data.link
+        }
+    }
+
+    #[test]
+    fn test_owning() {
+        let mut lst = Box::pin(DoublyLinkedList::new(XBoxLink));
+        for data in 1..=3 {
+            lst.as_mut().push_back(Box::new(X {
+                data,
+                link: Link::new(),
+            }));
+        }
+        let item = lst.as_mut().pop_front().unwrap();
+        assert_eq!(item.data, 1);
+        let mut cur = lst.as_mut().cursor_front_mut();
+        let item = cur.replace_current(item).unwrap();
+        assert_eq!(item.data, 2);
+        assert_eq!(collect_box(lst.as_ref()), [1, 3]);
+        lst.as_mut().clear();
+        assert!(lst.as_ref().is_empty());
+
+        for data in 1..=3 {
+            lst.as_mut().push_back(Box::new(X {
+                data,
+                link: Link::new(),
+            }));
+        }
+        let item = lst.as_mut().pop_back().unwrap();
+        assert_eq!(item.data, 3);
+        assert!(!item.link.is_linked());
+        drop(lst);
+    }
+
+    #[derive(Debug)]
+    struct XRcLink;
+
+    impl LinkAdapter<X> for XRcLink {
+        type Link = Link<X>;
+        type Size = NumerateSize;
+        type Pointer = Rc<X>;
+
+        fn link_ref(data: &X) -> &Self::Link {
+            &data.link
+        }
+
+        fn link_mut(data: &mut X) -> &mut Self::Link {
+            &mut data.link
+        }
+    }
+
+    #[test]
+    fn test_owning_rc() {
+        let items: Vec<_> = (1..=3)
+            .map(|data| {
+                Rc::new(X {
+                    data,
+                    link: Link::new(),
+                })
+            })
+            .collect();
+        let mut lst = Box::pin(DoublyLinkedList::new(XRcLink));
+        for item in &items {
+            lst.as_mut().push_back(item.clone());
+        }
+        assert!(items.iter().all(|x| Rc::strong_count(x) == 2));
+        // the other clones see the links the list wrote
+        assert!(items.iter().all(|x| x.link.is_linked()));
+        let item = lst.as_mut().pop_back().unwrap();
+        assert!(Rc::ptr_eq(&item, &items[2]));
+        drop(item);
+        assert_eq!(Rc::strong_count(&items[2]), 1);
+        assert!(!items[2].link.is_linked());
+        lst.as_mut().clear();
+        assert!(lst.as_ref().is_empty());
+        assert!(items.iter().all(|x| Rc::strong_count(x) == 1));
+        assert!(items.iter().all(|x| !x.link.is_linked()));
+
+        for item in &items {
+            lst.as_mut().push_front(item.clone());
+        }
+        let data: Vec<_> = lst.as_ref().iter().map(|x| x.data).collect();
+        assert_eq!(data, [3, 2, 1]);
+        drop(lst);
+        assert!(items.iter().all(|x| Rc::strong_count(x) == 1));
+        assert!(items.iter().all(|x| !x.link.is_linked()));
+    }
+
+    fn collect_box(lst: Pin<&DoublyLinkedList<X, XBoxLink, NonNull<X>>>) -> Vec<i32> {
+        lst.iter().map(|x| x.data).collect()
+    }
 }
@@ -1,9 +1,14 @@
+use std::cell::Cell;
 use std::fmt;
 use std::fmt::Formatter;
 use std::marker::{PhantomData, PhantomPinned};
-use std::ops::{Deref, DerefMut};
+use std::num::NonZeroIsize;
+use std::ops::Deref;
 use std::pin::Pin;
+use std::ptr;
 use std::ptr::NonNull;
+use std::rc::Rc;
+use std::sync::Arc;
 
 pub trait Pointer<T>: fmt::Debug {
     fn from_raw(raw_ptr: NonNull<T>, self_addr: usize) -> Self;
@@ -11,6 +16,10 @@ pub trait Pointer<T>: fmt::Debug {
     fn as_ref(&self) -> &T;
 
     fn as_mut(&mut self) -> &mut T;
+
+    fn to_raw(&self) -> NonNull<T> {
+        NonNull::from(self.as_ref())
+    }
 }
 
 impl<T> Pointer<T> for NonNull<T> {
@@ -25,8 +34,209 @@ impl<T> Pointer<T> for NonNull<T> {
     fn as_mut(&mut self) -> &mut T {
         unsafe { &mut *self.as_ptr() }
     }
+
+    fn to_raw(&self) -> NonNull<T> {
+        *self
+    }
+}
+
+/// A pointer kept as the distance from itself to its target.
+///
+/// Links built from it stay valid when the memory region holding both the
+/// links and their targets is mapped or copied to another base address, but
+/// not when a container header is moved on its own. The pointer is therefore
+/// `!Unpin`, and so is every container whose links use it: such a container
+/// has to be pinned before it holds elements.
+#[repr(transparent)]
+pub struct OffsetPtr<T> {
+    offset: NonZeroIsize,
+    _pin: PhantomPinned,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T> OffsetPtr<T> {
+    // The distance is stored off by one. The pointer lives inside a link,
+    // which is a field of a `T`, so both addresses are aligned to at least a
+    // pointer and can never be one byte apart; the stored value is never zero
+    // even when a link refers to the element that embeds it.
+    fn target(&self) -> *mut T {
+        let addr = (self as *const Self).addr();
+        ptr::with_exposed_provenance_mut(
+            addr.wrapping_add_signed(self.offset.get()).wrapping_add(1),
+        )
+    }
+}
+
+impl<T> Pointer<T> for OffsetPtr<T> {
+    fn from_raw(raw_ptr: NonNull<T>, self_addr: usize) -> Self {
+        let offset = raw_ptr
+            .as_ptr()
+            .expose_provenance()
+            .wrapping_sub(self_addr)
+            .wrapping_sub(1) as isize;
+        debug_assert_ne!(offset, 0);
+        Self {
+            offset: unsafe { NonZeroIsize::new_unchecked(offset) },
+            _pin: PhantomPinned,
+            _marker: PhantomData,
+        }
+    }
+
+    fn as_ref(&self) -> &T {
+        unsafe { &*self.target() }
+    }
+
+    fn as_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.target() }
+    }
+}
+
+impl<T> fmt::Debug for OffsetPtr<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let offset = self.offset.get().wrapping_add(1);
+        let sign = if offset < 0 { '-' } else { '+' };
+        write!(f, "{}{:#x}", sign, offset.unsigned_abs())
+    }
+}
+
+/// The byte offset of a link field within `T`, as computed by `offset_of!`.
+pub trait LinkOffset<T> {
+    const OFFSET: usize;
+}
+
+/// A pointer kept as the address of the link embedded in its target.
+///
+/// Links built from it point at each other rather than at the elements, as a
+/// C `list_head` does, and the element is recovered by subtracting the offset
+/// of the link field named by `O`.
+///
+/// Every link is `#[repr(C)]` and an empty pointer is null, so with `LinkPtr`
+/// a `list::Link` is laid out as `struct { next, prev }` and a
+/// `bintree::Link` as `struct { top, left, right }`, each field the address of
+/// another link; the balanced trees append their balance data after those.
+/// Unlike a `list_head`, a list is not circular: its ends hold null rather
+/// than the address of the header. The link type itself still names `T`, so
+/// one link type cannot be shared by unrelated element types.
+#[repr(transparent)]
+pub struct LinkPtr<T, O> {
+    link: NonNull<u8>,
+    _marker: PhantomData<(*mut T, O)>,
+}
+
+impl<T, O> LinkPtr<T, O>
+where
+    O: LinkOffset<T>,
+{
+    /// Returns the address of the link this pointer refers to.
+    pub fn link(&self) -> NonNull<u8> {
+        self.link
+    }
+
+    /// Recovers the element that embeds the link at `link`.
+    ///
+    /// # Safety
+    ///
+    /// `link` must point at the field named by `O` inside a live `T`.
+    pub unsafe fn container_of(link: NonNull<u8>) -> NonNull<T> {
+        link.byte_sub(O::OFFSET).cast()
+    }
+}
+
+impl<T, O> Pointer<T> for LinkPtr<T, O>
+where
+    O: LinkOffset<T>,
+{
+    fn from_raw(raw_ptr: NonNull<T>, _: usize) -> Self {
+        Self {
+            link: unsafe { raw_ptr.cast::<u8>().byte_add(O::OFFSET) },
+            _marker: PhantomData,
+        }
+    }
+
+    fn as_ref(&self) -> &T {
+        unsafe { Self::container_of(self.link).as_ref() }
+    }
+
+    fn as_mut(&mut self) -> &mut T {
+        unsafe { Self::container_of(self.link).as_mut() }
+    }
+}
+
+impl<T, O> fmt::Debug for LinkPtr<T, O> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.link)
+    }
+}
+
+/// The handle that moves an element into and out of a container.
+///
+/// A container holding owning handles releases its elements when it is
+/// cleared or dropped. Links keep their pointers in cells and containers
+/// rewrite them through shared references, so an element may also be
+/// reachable through other `Rc` or `Arc` clones while it is linked.
+pub trait OwningPointer<T> {
+    fn into_raw(self) -> NonNull<T>;
+
+    /// # Safety
+    ///
+    /// `raw_ptr` must come from `into_raw` of the same pointer type.
+    unsafe fn from_raw(raw_ptr: NonNull<T>) -> Self;
+}
+
+impl<T> OwningPointer<T> for NonNull<T> {
+    fn into_raw(self) -> NonNull<T> {
+        self
+    }
+
+    unsafe fn from_raw(raw_ptr: NonNull<T>) -> Self {
+        raw_ptr
+    }
 }
 
+impl<T> OwningPointer<T> for Box<T> {
+    fn into_raw(self) -> NonNull<T> {
+        NonNull::from(Box::leak(self))
+    }
+
+    unsafe fn from_raw(raw_ptr: NonNull<T>) -> Self {
+        Box::from_raw(raw_ptr.as_ptr())
+    }
+}
+
+impl<T> OwningPointer<T> for Rc<T> {
+    fn into_raw(self) -> NonNull<T> {
+        unsafe { NonNull::new_unchecked(Rc::into_raw(self).cast_mut()) }
+    }
+
+    unsafe fn from_raw(raw_ptr: NonNull<T>) -> Self {
+        Rc::from_raw(raw_ptr.as_ptr())
+    }
+}
+
+impl<T> OwningPointer<T> for Arc<T> {
+    fn into_raw(self) -> NonNull<T> {
+        unsafe { NonNull::new_unchecked(Arc::into_raw(self).cast_mut()) }
+    }
+
+    unsafe fn from_raw(raw_ptr: NonNull<T>) -> Self {
+        Arc::from_raw(raw_ptr.as_ptr())
+    }
+}
+
+/// An owning pointer that is the only handle to its element, so that a
+/// container holding it may hand out `&mut T`.
+///
+/// # Safety
+///
+/// No other reference to the element may exist while the container holds it.
+pub unsafe trait UniquePointer<T>: OwningPointer<T> {}
+
+unsafe impl<T> UniquePointer<T> for NonNull<T> {}
+
+unsafe impl<T> UniquePointer<T> for Box<T> {}
+
+// Transparent so that `ptr` lives at the address handed to `Pointer::from_raw`.
+#[repr(transparent)]
 pub(crate) struct NonNullPtr<T, P> {
     ptr: P,
     _pin: PhantomPinned,
@@ -38,28 +248,32 @@ where
     T: Unpin,
     P: Pointer<T>,
 {
-    pub fn assign(self_: &mut Option<Pin<Self>>, data: NonNull<T>) {
-        let ptr = self_ as *mut Option<Pin<Self>>;
-        let ptr = P::from_raw(data, ptr.addr());
+    pub fn assign(self_: &Cell<Option<Pin<Self>>>, data: NonNull<T>) {
+        let ptr = P::from_raw(data, self_.as_ptr().addr());
         let ptr = NonNullPtr {
-            ptr: ptr,
+            ptr,
             _pin: PhantomPinned,
             _marker: PhantomData,
         };
-        *self_ = Some(Pin::new(ptr));
-    }
-
-    pub fn assign_pin(self_: &mut Option<Pin<Self>>, data: &mut Pin<Self>) {
-        Self::assign(self_, NonNull::from(data.as_mut().get_mut()))
+        self_.set(Some(Pin::new(ptr)));
     }
 
-    pub fn assign_ptr(self_: &mut Option<Pin<Self>>, data: &mut Option<Pin<Self>>) {
+    pub fn assign_raw(self_: &Cell<Option<Pin<Self>>>, data: Option<NonNull<T>>) {
         if let Some(data) = data {
-            Self::assign_pin(self_, data)
+            Self::assign(self_, data)
         } else {
-            *self_ = None;
+            self_.set(None);
         }
     }
+
+    pub fn to_raw(self_: &Cell<Option<Pin<Self>>>) -> Option<NonNull<T>> {
+        // Only the containers write to the cell, and never while this runs.
+        unsafe { &*self_.as_ptr() }.as_ref().map(|data| {
+            // Pin<Self> is repr(transparent), so this only strips the Pin.
+            let data = unsafe { &*(data as *const Pin<Self> as *const Self) };
+            data.ptr.to_raw()
+        })
+    }
 }
 
 impl<T, P> Deref for NonNullPtr<T, P>
@@ -73,15 +287,6 @@ where
     }
 }
 
-impl<T, P> DerefMut for NonNullPtr<T, P>
-where
-    P: Pointer<T>,
-{
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.ptr.as_mut()
-    }
-}
-
 impl<T, P> fmt::Debug for NonNullPtr<T, P>
 where
     P: fmt::Debug,
@@ -90,3 +295,170 @@ where
         write!(f, "{:?}", self.ptr)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::adapter::NumerateSize;
+    use crate::bintree::{self, BinTree};
+    use crate::intrusive_adapter;
+    use crate::list::{self, DoublyLinkedList};
+    use std::mem;
+
+    struct X {
+        data: i32,
+        list_link: list::Link<Self, OffsetPtr<Self>>,
+        tree_link: bintree::Link<Self, OffsetPtr<Self>>,
+    }
+
+    intrusive_adapter! {
+        XListLink = X { list_link: list::Link<X, OffsetPtr<X>> } with NumerateSize;
+        XTreeLink = X { tree_link: bintree::Link<X, OffsetPtr<X>>, key = data: i32 } with NumerateSize;
+    }
+
+    struct Y {
+        data: i32,
+        list_link: list::Link<Self, LinkPtr<Self, YListLink>>,
+        tree_link: bintree::Link<Self, LinkPtr<Self, YTreeLink>>,
+    }
+
+    intrusive_adapter! {
+        YListLink = Y { list_link: list::Link<Y, LinkPtr<Y, YListLink>> } with NumerateSize;
+        YTreeLink = Y { tree_link: bintree::Link<Y, LinkPtr<Y, YTreeLink>>, key = data: i32 } with NumerateSize;
+    }
+
+    // everything the containers point at lives in one block, like a mapped segment
+    struct Region {
+        list: DoublyLinkedList<X, XListLink, OffsetPtr<X>>,
+        tree: BinTree<X, XTreeLink, OffsetPtr<X>>,
+        items: [X; 5],
+    }
+
+    #[test]
+    fn test_offset_ptr_size() {
+        assert_eq!(
+            mem::size_of::<Option<Pin<NonNullPtr<X, OffsetPtr<X>>>>>(),
+            mem::size_of::<usize>()
+        );
+    }
+
+    #[test]
+    fn test_link_ptr_layout() {
+        let word = mem::size_of::<usize>();
+        assert_eq!(
+            mem::size_of::<list::Link<Y, LinkPtr<Y, YListLink>>>(),
+            2 * word
+        );
+        assert_eq!(
+            mem::size_of::<bintree::Link<Y, LinkPtr<Y, YTreeLink>>>(),
+            3 * word
+        );
+        assert_eq!(
+            mem::size_of::<Option<Pin<NonNullPtr<Y, LinkPtr<Y, YListLink>>>>>(),
+            word
+        );
+    }
+
+    #[test]
+    fn test_offset_ptr_self() {
+        type SelfPtr = Option<Pin<NonNullPtr<S, OffsetPtr<S>>>>;
+
+        #[repr(C)]
+        struct S {
+            ptr: Cell<SelfPtr>,
+        }
+
+        impl Unpin for S {}
+
+        let s = Box::new(S {
+            ptr: Cell::new(None),
+        });
+        let target = NonNull::from(&*s);
+        NonNullPtr::assign(&s.ptr, target);
+        assert_eq!(NonNullPtr::to_raw(&s.ptr), Some(target));
+    }
+
+    #[test]
+    fn test_offset_ptr_relocate() {
+        let mut region = Box::new(Region {
+            list: DoublyLinkedList::new(XListLink),
+            tree: BinTree::new(XTreeLink),
+            items: [3, 1, 4, 5, 2].map(|data| X {
+                data,
+                list_link: list::Link::new(),
+                tree_link: bintree::Link::new(),
+            }),
+        });
+        let Region { list, tree, items } = &mut *region;
+        for item in items {
+            let item = NonNull::from(item);
+            unsafe { Pin::new_unchecked(&mut *list) }.push_back(item);
+            assert!(unsafe { Pin::new_unchecked(&mut *tree) }
+                .insert(item)
+                .is_none());
+        }
+
+        let mut copy = Box::<Region>::new_uninit();
+        let copy = unsafe {
+            ptr::copy_nonoverlapping(&*region, copy.as_mut_ptr(), 1);
+            ptr::write_bytes(&mut *region as *mut Region, 0, 1);
+            copy.assume_init()
+        };
+        drop(region);
+
+        let range = (&*copy as *const Region).addr()
+            ..(&*copy as *const Region).addr() + mem::size_of::<Region>();
+        let list = unsafe { Pin::new_unchecked(&copy.list) };
+        let data: Vec<_> = list.iter().map(|x| x.data).collect();
+        assert_eq!(data, [3, 1, 4, 5, 2]);
+        let data: Vec<_> = list.iter().rev().map(|x| x.data).collect();
+        assert_eq!(data, [2, 5, 4, 1, 3]);
+        assert!(list
+            .iter()
+            .all(|x| range.contains(&(x.get_ref() as *const X).addr())));
+
+        let tree = unsafe { Pin::new_unchecked(&copy.tree) };
+        let data: Vec<_> = tree.iter().map(|x| x.data).collect();
+        assert_eq!(data, [1, 2, 3, 4, 5]);
+        assert!(tree
+            .iter()
+            .all(|x| range.contains(&(x.get_ref() as *const X).addr())));
+    }
+
+    #[test]
+    fn test_link_ptr() {
+        let mut items = [3, 1, 2].map(|data| Y {
+            data,
+            list_link: list::Link::new(),
+            tree_link: bintree::Link::new(),
+        });
+        let mut lst = Box::pin(DoublyLinkedList::new(YListLink));
+        let mut tree = Box::pin(BinTree::new(YTreeLink));
+        for item in &mut items {
+            let item = NonNull::from(item);
+            lst.as_mut().push_back(item);
+            assert!(tree.as_mut().insert(item).is_none());
+        }
+
+        let data: Vec<_> = lst.as_ref().iter().map(|y| y.data).collect();
+        assert_eq!(data, [3, 1, 2]);
+        let data: Vec<_> = lst.as_ref().iter().rev().map(|y| y.data).collect();
+        assert_eq!(data, [2, 1, 3]);
+        let data: Vec<_> = tree.as_ref().iter().map(|y| y.data).collect();
+        assert_eq!(data, [1, 2, 3]);
+
+        // the list links hold the addresses of their neighbours' links
+        let raw = |item: &Y| unsafe { *(&item.list_link as *const _ as *const [*const u8; 2]) };
+        let link_of = |item: &Y| &item.list_link as *const _ as *const u8;
+        assert_eq!(raw(&items[0]), [link_of(&items[1]), ptr::null()]);
+        assert_eq!(raw(&items[1]), [link_of(&items[2]), link_of(&items[0])]);
+        assert_eq!(raw(&items[2]), [ptr::null(), link_of(&items[1])]);
+
+        for item in &items {
+            let link = LinkPtr::<Y, YTreeLink>::from_raw(NonNull::from(item), 0).link();
+            assert_eq!(link, NonNull::from(&item.tree_link).cast());
+            let owner = unsafe { LinkPtr::<Y, YTreeLink>::container_of(link) };
+            assert_eq!(owner, NonNull::from(item));
+        }
+    }
+}
@@ -0,0 +1,879 @@
+use crate::adapter::{KeyAdapter, LinkAdapter, NaturalOrder, Size};
+use crate::bintree::{self, AsTreeLink, Balance, TreeLink};
+use crate::ptr::{OwningPointer, Pointer, UniquePointer};
+use std::borrow::Borrow;
+use std::cell::Cell;
+use std::cmp;
+use std::fmt;
+use std::mem;
+use std::pin::Pin;
+use std::ptr::NonNull;
+
+pub type Iter<'a, T, A, P> = bintree::Iter<'a, T, TreeLink<A>, P>;
+
+pub type IterMut<'a, T, A, P> = bintree::IterMut<'a, T, TreeLink<A>, P>;
+
+pub type Union<'a, T, A, P> = bintree::Union<'a, T, TreeLink<A>, P, NaturalOrder>;
+
+pub type Intersection<'a, T, A, P> = bintree::Intersection<'a, T, TreeLink<A>, P, NaturalOrder>;
+
+pub type Difference<'a, T, A, P> = bintree::Difference<'a, T, TreeLink<A>, P, NaturalOrder>;
+
+pub type SymmetricDifference<'a, T, A, P> =
+    bintree::SymmetricDifference<'a, T, TreeLink<A>, P, NaturalOrder>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Color {
+    Red,
+    Black,
+}
+
+/// A `bintree::Link` that also carries the color of its node, which is
+/// `None` while the node is in no tree.
+#[repr(C)]
+pub struct Link<T, P = NonNull<T>> {
+    tree: bintree::Link<T, P>,
+    color: Cell<Option<Color>>,
+}
+
+impl<T, P> Link<T, P> {
+    pub const fn new() -> Self {
+        Self {
+            tree: bintree::Link::new(),
+            color: Cell::new(None),
+        }
+    }
+
+    pub const fn is_linked(&self) -> bool {
+        unsafe { (*self.color.as_ptr()).is_some() }
+    }
+}
+
+impl<T, P> AsTreeLink<T> for Link<T, P> {
+    type Pointer = P;
+
+    fn tree_ref<A>(data: &T) -> &bintree::Link<T, P>
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        &A::link_ref(data).tree
+    }
+
+    fn tree_mut<A>(data: &mut T) -> &mut bintree::Link<T, P>
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        &mut A::link_mut(data).tree
+    }
+}
+
+impl<T, P> Link<T, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+{
+    fn of<'a, A>(node: NonNull<T>) -> &'a Self
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        A::link_ref(unsafe { &*node.as_ptr() })
+    }
+
+    fn tree<'a, A>(node: NonNull<T>) -> &'a bintree::Link<T, P>
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        bintree::Link::of::<TreeLink<A>>(node)
+    }
+
+    fn is_red<A>(node: Option<NonNull<T>>) -> bool
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        node.is_some_and(|node| Self::of::<A>(node).color.get() == Some(Color::Red))
+    }
+
+    fn set_color<A>(node: NonNull<T>, color: Color)
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        Self::of::<A>(node).color.set(Some(color));
+    }
+
+    /// Links `node` as a red leaf at `slot` and restores the red-black rules.
+    fn link_leaf<A>(tree: &mut bintree::Link<T, P>, slot: &bintree::Slot<T>, node: NonNull<T>)
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        tree.link_leaf::<TreeLink<A>>(slot, node);
+        Self::set_color::<A>(node, Color::Red);
+        Self::insert_fixup::<A>(tree, node);
+    }
+
+    fn insert_fixup<A>(tree: &mut bintree::Link<T, P>, mut node: NonNull<T>)
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        while let Some(mut top) = Self::tree::<A>(node)
+            .top()
+            .filter(|top| Self::is_red::<A>(Some(*top)))
+        {
+            // a red node is never the root, so the grandparent exists
+            let grand = Self::tree::<A>(top).top().unwrap();
+            let top_is_left = Self::tree::<A>(grand).left() == Some(top);
+            let uncle = if top_is_left {
+                Self::tree::<A>(grand).right()
+            } else {
+                Self::tree::<A>(grand).left()
+            };
+            if Self::is_red::<A>(uncle) {
+                Self::set_color::<A>(top, Color::Black);
+                Self::set_color::<A>(uncle.unwrap(), Color::Black);
+                Self::set_color::<A>(grand, Color::Red);
+                node = grand;
+                continue;
+            }
+            // an inner grandchild is first rotated to the outside, where
+            // it and its parent trade places
+            if top_is_left && Self::tree::<A>(top).right() == Some(node) {
+                tree.rotate_left::<TreeLink<A>>(top);
+                mem::swap(&mut node, &mut top);
+            } else if !top_is_left && Self::tree::<A>(top).left() == Some(node) {
+                tree.rotate_right::<TreeLink<A>>(top);
+                mem::swap(&mut node, &mut top);
+            }
+            Self::set_color::<A>(top, Color::Black);
+            Self::set_color::<A>(grand, Color::Red);
+            if top_is_left {
+                tree.rotate_right::<TreeLink<A>>(grand);
+            } else {
+                tree.rotate_left::<TreeLink<A>>(grand);
+            }
+        }
+        if let Some(root) = tree.top() {
+            Self::set_color::<A>(root, Color::Black);
+        }
+    }
+
+    /// Unlinks `node` and restores the black height if a black node left its place.
+    fn unlink_node<A>(tree: &mut bintree::Link<T, P>, node: NonNull<T>)
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        let color = Self::of::<A>(node).color.take();
+        let vacancy = tree.unlink_node::<TreeLink<A>>(node);
+        // a spliced successor takes over the color of node, so the color
+        // missing from the tree is the one it had in its old place
+        let color = match vacancy.next {
+            Some(next) => Self::of::<A>(next).color.replace(color),
+            None => color,
+        };
+        if color == Some(Color::Black) {
+            Self::remove_fixup::<A>(tree, vacancy.child, vacancy.top);
+        }
+    }
+
+    /// Restores the black height after a black node was removed above `node`.
+    fn remove_fixup<A>(
+        tree: &mut bintree::Link<T, P>,
+        mut node: Option<NonNull<T>>,
+        mut top: Option<NonNull<T>>,
+    ) where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        while node != tree.top() && !Self::is_red::<A>(node) {
+            // a non-root node always has a parent, and a sibling because of the missing black
+            let parent = top.unwrap();
+            let is_left = Self::tree::<A>(parent).left() == node;
+            let sibling_of = |parent| {
+                let link = Self::tree::<A>(parent);
+                if is_left {
+                    link.right().unwrap()
+                } else {
+                    link.left().unwrap()
+                }
+            };
+            let mut sibling = sibling_of(parent);
+            if Self::is_red::<A>(Some(sibling)) {
+                Self::set_color::<A>(sibling, Color::Black);
+                Self::set_color::<A>(parent, Color::Red);
+                Self::rotate_away::<A>(tree, parent, is_left);
+                sibling = sibling_of(parent);
+            }
+            let (near, far) = {
+                let link = Self::tree::<A>(sibling);
+                if is_left {
+                    (link.left(), link.right())
+                } else {
+                    (link.right(), link.left())
+                }
+            };
+            if !Self::is_red::<A>(near) && !Self::is_red::<A>(far) {
+                Self::set_color::<A>(sibling, Color::Red);
+                node = Some(parent);
+                top = Self::tree::<A>(parent).top();
+                continue;
+            }
+            if !Self::is_red::<A>(far) {
+                Self::set_color::<A>(near.unwrap(), Color::Black);
+                Self::set_color::<A>(sibling, Color::Red);
+                Self::rotate_away::<A>(tree, sibling, !is_left);
+                sibling = sibling_of(parent);
+            }
+            Self::of::<A>(sibling)
+                .color
+                .set(Self::of::<A>(parent).color.get());
+            Self::set_color::<A>(parent, Color::Black);
+            let far = if is_left {
+                Self::tree::<A>(sibling).right()
+            } else {
+                Self::tree::<A>(sibling).left()
+            };
+            Self::set_color::<A>(far.unwrap(), Color::Black);
+            Self::rotate_away::<A>(tree, parent, is_left);
+            node = tree.top();
+            break;
+        }
+        if let Some(node) = node {
+            Self::set_color::<A>(node, Color::Black);
+        }
+    }
+
+    /// Rotates `node` down towards its left child if `is_left`, else its right.
+    fn rotate_away<A>(tree: &mut bintree::Link<T, P>, node: NonNull<T>, is_left: bool)
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        if is_left {
+            tree.rotate_left::<TreeLink<A>>(node);
+        } else {
+            tree.rotate_right::<TreeLink<A>>(node);
+        }
+    }
+
+    /// Unlinks every node below the header, releasing the owned ones.
+    fn clear<A>(tree: &mut bintree::Link<T, P>)
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        tree.drain::<TreeLink<A>>(|node| {
+            Self::of::<A>(node).color.set(None);
+            drop(unsafe { A::Pointer::from_raw(node) });
+        });
+    }
+}
+
+impl<T, P> Default for Link<T, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, P> Unpin for Link<T, P> where T: Unpin {}
+
+impl<T, P> cmp::PartialEq for Link<T, P> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<T, P> cmp::Eq for Link<T, P> {}
+
+impl<T, P> cmp::PartialOrd for Link<T, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, P> cmp::Ord for Link<T, P> {
+    fn cmp(&self, _: &Self) -> cmp::Ordering {
+        cmp::Ordering::Equal
+    }
+}
+
+impl<T, P> fmt::Debug for Link<T, P>
+where
+    P: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} {:?}", self.tree, self.color.get())
+    }
+}
+
+#[derive(Debug)]
+pub struct RBTree<T, A, P = NonNull<T>>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    size: A::Size,
+    link: bintree::Link<T, P>,
+}
+
+impl<T, A, P> RBTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    pub fn new(_: A) -> Self {
+        Self {
+            size: Default::default(),
+            link: bintree::Link::new(),
+        }
+    }
+}
+
+impl<T, A, P> RBTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    A::Key: cmp::Ord,
+{
+    pub fn get<Q>(self: Pin<&Self>, key: &Q) -> Option<Pin<&T>>
+    where
+        A::Key: Borrow<Q>,
+        Q: cmp::Ord + ?Sized,
+    {
+        let self_ = Pin::get_ref(self);
+        let node = self_
+            .link
+            .search::<TreeLink<A>, Q, NaturalOrder>(key, &NaturalOrder)
+            .ok()?;
+        Some(Pin::new(unsafe { &*node.as_ptr() }))
+    }
+
+    pub fn get_mut<Q>(self: Pin<&mut Self>, key: &Q) -> Option<Pin<&mut T>>
+    where
+        A::Key: Borrow<Q>,
+        Q: cmp::Ord + ?Sized,
+        A::Pointer: UniquePointer<T>,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_
+            .link
+            .search::<TreeLink<A>, Q, NaturalOrder>(key, &NaturalOrder)
+            .ok()?;
+        Some(Pin::new(unsafe { &mut *node.as_ptr() }))
+    }
+
+    pub fn insert(self: Pin<&mut Self>, item: A::Pointer) -> Option<A::Pointer> {
+        let item = item.into_raw();
+        assert!(
+            !Link::of::<A>(item).is_linked(),
+            "the element is already linked"
+        );
+
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let key = A::get_key(unsafe { item.as_ref() });
+        match self_
+            .link
+            .search::<TreeLink<A>, A::Key, NaturalOrder>(key, &NaturalOrder)
+        {
+            Ok(_) => Some(unsafe { A::Pointer::from_raw(item) }),
+            Err(slot) => {
+                Link::link_leaf::<A>(&mut self_.link, &slot, item);
+                self_.size.incr();
+                None
+            }
+        }
+    }
+
+    pub fn remove<Q>(self: Pin<&mut Self>, key: &Q) -> Option<A::Pointer>
+    where
+        A::Key: Borrow<Q>,
+        Q: cmp::Ord + ?Sized,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_
+            .link
+            .search::<TreeLink<A>, Q, NaturalOrder>(key, &NaturalOrder)
+            .ok()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn union<'a>(self: Pin<&'a Self>, other: Pin<&'a Self>) -> Union<'a, T, A, P> {
+        bintree::Union(self.merge(other))
+    }
+
+    pub fn intersection<'a>(
+        self: Pin<&'a Self>,
+        other: Pin<&'a Self>,
+    ) -> Intersection<'a, T, A, P> {
+        bintree::Intersection(self.merge(other))
+    }
+
+    pub fn difference<'a>(self: Pin<&'a Self>, other: Pin<&'a Self>) -> Difference<'a, T, A, P> {
+        bintree::Difference(self.merge(other))
+    }
+
+    pub fn symmetric_difference<'a>(
+        self: Pin<&'a Self>,
+        other: Pin<&'a Self>,
+    ) -> SymmetricDifference<'a, T, A, P> {
+        bintree::SymmetricDifference(self.merge(other))
+    }
+
+    fn merge<'a>(
+        self: Pin<&'a Self>,
+        other: Pin<&'a Self>,
+    ) -> bintree::Merge<'a, T, TreeLink<A>, P, NaturalOrder> {
+        bintree::Merge::new(self.iter(), other.iter(), &NaturalOrder)
+    }
+
+    /// Moves the elements of `other` whose keys are missing here into this tree.
+    ///
+    /// Afterwards `other` holds only the elements whose keys both trees held.
+    pub fn union_with(self: Pin<&mut Self>, other: Pin<&mut Self>) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let other = unsafe { Pin::get_unchecked_mut(other) };
+        self_.link.union_with::<TreeLink<A>, NaturalOrder, Self>(
+            &mut self_.size,
+            &mut other.link,
+            &mut other.size,
+            &NaturalOrder,
+        );
+    }
+
+    /// Moves the elements whose keys `other` also holds into the empty tree `into`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `into` is not empty.
+    pub fn extract_intersection(self: Pin<&mut Self>, other: Pin<&Self>, into: Pin<&mut Self>) {
+        self.extract(other, into, true)
+    }
+
+    /// Moves the elements whose keys `other` lacks into the empty tree `into`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `into` is not empty.
+    pub fn extract_difference(self: Pin<&mut Self>, other: Pin<&Self>, into: Pin<&mut Self>) {
+        self.extract(other, into, false)
+    }
+
+    fn extract(self: Pin<&mut Self>, other: Pin<&Self>, into: Pin<&mut Self>, shared: bool) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let other = Pin::get_ref(other);
+        let into = unsafe { Pin::get_unchecked_mut(into) };
+        self_.link.extract::<TreeLink<A>, NaturalOrder, Self>(
+            &mut self_.size,
+            &other.link,
+            &NaturalOrder,
+            &mut into.link,
+            &mut into.size,
+            shared,
+        );
+    }
+}
+
+impl<T, A, P> RBTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn unlink(&mut self, node: NonNull<T>) -> A::Pointer {
+        Link::unlink_node::<A>(&mut self.link, node);
+        self.size.decr();
+        unsafe { A::Pointer::from_raw(node) }
+    }
+
+    /// Unlinks every element, releasing the owned ones.
+    pub fn clear(self: Pin<&mut Self>) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        Link::clear::<A>(&mut self_.link);
+        self_.size = Default::default();
+    }
+
+    pub fn pop_front(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.left()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn pop_back(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.right()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn front(self: Pin<&Self>) -> Option<&T> {
+        Pin::get_ref(self).link.front()
+    }
+
+    pub fn front_mut(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.front_mut()
+    }
+
+    pub fn back(self: Pin<&Self>) -> Option<&T> {
+        Pin::get_ref(self).link.back()
+    }
+
+    pub fn back_mut(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.back_mut()
+    }
+
+    pub fn iter(self: Pin<&Self>) -> Iter<'_, T, A, P> {
+        Pin::get_ref(self).link.iter()
+    }
+
+    pub fn iter_mut(self: Pin<&mut Self>) -> IterMut<'_, T, A, P>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.iter_mut()
+    }
+
+    pub fn len(self: Pin<&Self>) -> usize {
+        self.link.len::<TreeLink<A>>(&self.size)
+    }
+
+    pub fn is_empty(self: Pin<&Self>) -> bool {
+        self.link.is_empty::<TreeLink<A>>(&self.size)
+    }
+}
+
+impl<T, A, P> Default for RBTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>> + Default,
+{
+    fn default() -> Self {
+        Self::new(A::default())
+    }
+}
+
+impl<T, A, P> Balance<T, P> for RBTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn link_leaf(tree: &mut bintree::Link<T, P>, slot: &bintree::Slot<T>, node: NonNull<T>) {
+        Link::link_leaf::<A>(tree, slot, node);
+    }
+
+    fn unlink_node(tree: &mut bintree::Link<T, P>, node: NonNull<T>) {
+        Link::unlink_node::<A>(tree, node);
+    }
+}
+
+impl<T, A, P> Drop for RBTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn drop(&mut self) {
+        if mem::needs_drop::<A::Pointer>() {
+            unsafe { Pin::new_unchecked(self) }.clear();
+        }
+    }
+}
+
+impl<T, A, P> Unpin for RBTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T> + Unpin,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::adapter::LiterateSize;
+    use crate::tree_test::{self, tree_fixture};
+
+    tree_fixture!(LiterateSize);
+
+    type Tree = RBTree<X, XLink>;
+
+    fn black_height(node: Option<NonNull<X>>) -> usize {
+        let Some(node) = node else {
+            return 1;
+        };
+        let link = Link::of::<XLink>(node);
+        let left = black_height(link.tree.left());
+        assert_eq!(left, black_height(link.tree.right()));
+        left + (link.color.get() == Some(Color::Black)) as usize
+    }
+
+    /// Checks the links, the key order and the red-black rules: the root is
+    /// black, no red node has a red child, and every path down meets the
+    /// same number of black nodes. Returns the height.
+    fn check(tree: Pin<&Tree>) -> usize {
+        let top = tree.link.top();
+        assert!(!Link::is_red::<XLink>(top));
+        let height = tree_test::check::<X, TreeLink<XLink>, _>(top, None, &mut |node| {
+            let link = Link::of::<XLink>(node);
+            assert!(link.is_linked());
+            if link.color.get() == Some(Color::Red) {
+                assert!(!Link::is_red::<XLink>(link.tree.left()));
+                assert!(!Link::is_red::<XLink>(link.tree.right()));
+            }
+        });
+        // no path is more than twice as long as the count of black nodes on it
+        assert!(height <= 2 * (black_height(top) - 1));
+        height
+    }
+
+    /// Lists the keys in order, each with `b` or `r` for its color.
+    fn colors(tree: Pin<&Tree>) -> Vec<(i32, char)> {
+        tree.iter()
+            .map(|x| {
+                let color = if x.link.color.get() == Some(Color::Red) {
+                    'r'
+                } else {
+                    'b'
+                };
+                (x.data, color)
+            })
+            .collect()
+    }
+
+    // deterministic permutation of 0..n
+    fn shuffle(n: i32) -> Vec<i32> {
+        let mut seed = 12345u32;
+        let mut vec: Vec<_> = (0..n).collect();
+        for i in (1..vec.len()).rev() {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            vec.swap(i, (seed >> 16) as usize % (i + 1));
+        }
+        vec
+    }
+
+    #[test]
+    fn test_link_size() {
+        // the color takes a word beside the three pointers: a generic `P`
+        // leaves no bit of its own to borrow
+        assert_eq!(mem::size_of::<Link<X>>(), 4 * mem::size_of::<usize>());
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut tree = Box::pin(RBTree::new(XLink));
+        assert_eq!(tree.as_ref().len(), 0);
+        assert!(tree.as_ref().is_empty());
+        assert!(tree.as_ref().front().is_none());
+        assert!(tree.as_ref().iter().next_back().is_none());
+        assert!(tree.as_mut().pop_front().is_none());
+        assert!(tree.as_mut().remove(&1).is_none());
+        assert_eq!(check(tree.as_ref()), 0);
+    }
+
+    #[test]
+    fn test_insert_recolors_and_rotates() {
+        let mut tree = Box::pin(RBTree::new(XLink));
+        for i in [2, 1, 3] {
+            tree.as_mut().insert(X::new(i));
+        }
+        assert_eq!(colors(tree.as_ref()), [(1, 'r'), (2, 'b'), (3, 'r')]);
+
+        // a red uncle: the parent and uncle turn black, the grandparent red
+        // and then black again as the root
+        tree.as_mut().insert(X::new(4));
+        assert_eq!(
+            colors(tree.as_ref()),
+            [(1, 'b'), (2, 'b'), (3, 'b'), (4, 'r')]
+        );
+
+        // a black uncle on the outside: one rotation at the grandparent
+        tree.as_mut().insert(X::new(5));
+        assert_eq!(
+            colors(tree.as_ref()),
+            [(1, 'b'), (2, 'b'), (3, 'r'), (4, 'b'), (5, 'r')]
+        );
+        check(tree.as_ref());
+
+        // a black uncle on the inside: rotated outside first
+        let mut tree = Box::pin(RBTree::new(XLink));
+        for i in [3, 1, 2] {
+            tree.as_mut().insert(X::new(i));
+        }
+        assert_eq!(tree.link.top().map(|x| unsafe { x.as_ref() }.data), Some(2));
+        assert_eq!(colors(tree.as_ref()), [(1, 'r'), (2, 'b'), (3, 'r')]);
+    }
+
+    #[test]
+    fn test_insert_sorted() {
+        let mut tree = Box::pin(RBTree::new(XLink));
+        for i in 0..1000 {
+            assert!(tree.as_mut().insert(X::new(i)).is_none());
+            check(tree.as_ref());
+        }
+        assert_eq!(tree.as_ref().len(), 1000);
+        assert_eq!(tree.as_ref().front().unwrap().data, 0);
+        assert_eq!(tree.as_ref().back().unwrap().data, 999);
+        assert_eq!(data(tree.as_ref()), (0..1000).collect::<Vec<_>>());
+        assert!(tree
+            .as_ref()
+            .iter()
+            .rev()
+            .map(|x| x.data)
+            .eq((0..1000).rev()));
+
+        let dup = tree.as_mut().insert(X::new(10)).unwrap();
+        assert!(!dup.link.is_linked());
+
+        while let Some(item) = tree.as_mut().pop_back() {
+            assert_eq!(item.data, tree.as_ref().len() as i32);
+            assert!(!item.link.is_linked());
+            check(tree.as_ref());
+        }
+    }
+
+    #[test]
+    fn test_remove_fixup() {
+        let mut tree = Box::pin(RBTree::new(XLink));
+        for i in 1..=7 {
+            tree.as_mut().insert(X::new(i));
+        }
+        assert_eq!(
+            colors(tree.as_ref()),
+            [
+                (1, 'b'),
+                (2, 'b'),
+                (3, 'b'),
+                (4, 'r'),
+                (5, 'r'),
+                (6, 'b'),
+                (7, 'r')
+            ]
+        );
+
+        // a red leaf leaves without any fixup
+        tree.as_mut().remove(&7);
+        check(tree.as_ref());
+        // a black leaf with a red sibling, which is rotated up first and
+        // leaves a black sibling with black children to turn red
+        tree.as_mut().remove(&1);
+        assert_eq!(tree.link.top().map(|x| unsafe { x.as_ref() }.data), Some(4));
+        assert_eq!(
+            colors(tree.as_ref()),
+            [(2, 'b'), (3, 'r'), (4, 'b'), (5, 'r'), (6, 'b')]
+        );
+        check(tree.as_ref());
+        // an inner node, replaced by its successor
+        tree.as_mut().remove(&4);
+        check(tree.as_ref());
+        assert_eq!(data(tree.as_ref()), [2, 3, 5, 6]);
+    }
+
+    #[test]
+    fn test_insert_remove_shuffled() {
+        let mut tree = Box::pin(RBTree::new(XLink));
+        for i in shuffle(500) {
+            tree.as_mut().insert(X::new(i));
+        }
+        check(tree.as_ref());
+        assert_eq!(data(tree.as_ref()), (0..500).collect::<Vec<_>>());
+        assert_eq!(tree.as_ref().get(&123).unwrap().data, 123);
+        RBTree::get_mut(tree.as_mut(), &7).unwrap().data = 7;
+
+        for i in shuffle(500) {
+            let item = tree.as_mut().remove(&i).unwrap();
+            assert_eq!(item.data, i);
+            assert!(tree.as_ref().get(&i).is_none());
+            check(tree.as_ref());
+        }
+        assert!(tree.as_ref().is_empty());
+    }
+
+    #[test]
+    fn test_iter_double_ended() {
+        let mut tree = Box::pin(RBTree::new(XLink));
+        for i in [3, 1, 4, 5, 2] {
+            tree.as_mut().insert(X::new(i));
+        }
+        let mut it = tree.as_ref().iter();
+        assert_eq!(it.next_back().unwrap().data, 5);
+        assert_eq!(it.next().unwrap().data, 1);
+        assert_eq!(it.next_back().unwrap().data, 4);
+        assert_eq!(it.next_back().unwrap().data, 3);
+        assert_eq!(it.next().unwrap().data, 2);
+        assert!(it.next().is_none());
+        assert!(it.next_back().is_none());
+
+        for mut x in tree.as_mut().iter_mut() {
+            x.data += 10;
+        }
+        assert_eq!(data(tree.as_ref()), [11, 12, 13, 14, 15]);
+        tree.as_mut().clear();
+        assert!(tree.as_ref().is_empty());
+    }
+
+    #[test]
+    fn test_set_algebra() {
+        fn keys<'a>(it: impl Iterator<Item = Pin<&'a X>>) -> Vec<i32> {
+            it.map(|x| x.data).collect()
+        }
+
+        let mut a = Box::pin(RBTree::new(XLink));
+        let mut b = Box::pin(RBTree::new(XLink));
+        for i in shuffle(100) {
+            a.as_mut().insert(X::new(i));
+        }
+        for i in (0..150).step_by(3) {
+            b.as_mut().insert(X::new(i));
+        }
+        let (a_, b_) = (a.as_ref(), b.as_ref());
+        assert_eq!(a_.union(b_).count(), 116);
+        assert_eq!(
+            keys(a_.intersection(b_)),
+            (0..100).step_by(3).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            keys(b_.difference(a_)),
+            (102..150).step_by(3).collect::<Vec<_>>()
+        );
+        assert_eq!(a_.symmetric_difference(b_).count(), 66 + 16);
+
+        let mut c = Box::pin(RBTree::new(XLink));
+        a.as_mut().extract_difference(b.as_ref(), c.as_mut());
+        check(a.as_ref());
+        check(c.as_ref());
+        assert_eq!(data(a.as_ref()), (0..100).step_by(3).collect::<Vec<_>>());
+        assert_eq!(
+            data(c.as_ref()),
+            (0..100).filter(|i| i % 3 != 0).collect::<Vec<_>>()
+        );
+        assert_eq!(c.as_ref().len(), 66);
+
+        a.as_mut().union_with(b.as_mut());
+        check(a.as_ref());
+        check(b.as_ref());
+        assert_eq!(data(a.as_ref()), (0..150).step_by(3).collect::<Vec<_>>());
+        assert_eq!(data(b.as_ref()), (0..100).step_by(3).collect::<Vec<_>>());
+
+        let mut d = Box::pin(RBTree::new(XLink));
+        a.as_mut().extract_intersection(b.as_ref(), d.as_mut());
+        check(a.as_ref());
+        check(d.as_ref());
+        assert_eq!(data(d.as_ref()), (0..100).step_by(3).collect::<Vec<_>>());
+        assert_eq!(data(a.as_ref()), (102..150).step_by(3).collect::<Vec<_>>());
+        assert_eq!(a.as_ref().len(), 16);
+    }
+
+    #[test]
+    #[should_panic(expected = "not empty")]
+    fn test_extract_into_nonempty() {
+        let mut a = Box::pin(RBTree::new(XLink));
+        let b = Box::pin(RBTree::new(XLink));
+        let mut c = Box::pin(RBTree::new(XLink));
+        a.as_mut().insert(X::new(1));
+        c.as_mut().insert(X::new(2));
+        a.as_mut().extract_difference(b.as_ref(), c.as_mut());
+    }
+}
@@ -0,0 +1,403 @@
+use crate::adapter::{Compare, KeyAdapter, LinkAdapter, NaturalOrder};
+use crate::ptr::{OwningPointer, Pointer, UniquePointer};
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::RangeBounds;
+use std::pin::Pin;
+use std::ptr::NonNull;
+
+pub use crate::bintree::{Iter, IterMut, Link, Range};
+
+/// The weight balance `numerator / denominator` that no subtree may exceed.
+const ALPHA: (usize, usize) = (2, 3);
+
+/// A scapegoat tree: balanced by rebuilding whole subtrees, so its nodes
+/// carry nothing beyond the three pointers of a `bintree::Link`.
+///
+/// The tree counts its elements in the header whatever the adapter's `Size`,
+/// since the depth bound is checked against the count on every insert.
+pub struct SGTree<T, A, P = NonNull<T>, C = NaturalOrder>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    len: usize,
+    max_len: usize,
+    link: Link<T, P>,
+    compare: C,
+    _marker: PhantomData<A>,
+}
+
+impl<T, A, P> SGTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    pub fn new(adapter: A) -> Self {
+        Self::with_comparator(adapter, NaturalOrder)
+    }
+}
+
+impl<T, A, P, C> SGTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    /// Creates a tree that orders its keys by `compare` instead of `Ord`.
+    ///
+    /// A closure compares `A::Key` only, so lookups then take `&A::Key`
+    /// rather than a borrowed form of it; see [`Compare`].
+    pub fn with_comparator(_: A, compare: C) -> Self {
+        Self {
+            len: 0,
+            max_len: 0,
+            link: Link::new(),
+            compare,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, A, P, C> SGTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    C: Compare<A::Key>,
+{
+    pub fn get<Q>(self: Pin<&Self>, key: &Q) -> Option<Pin<&T>>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let self_ = Pin::get_ref(self);
+        let node = self_.link.search::<A, Q, C>(key, &self_.compare).ok()?;
+        Some(Pin::new(unsafe { &*node.as_ptr() }))
+    }
+
+    pub fn get_mut<Q>(self: Pin<&mut Self>, key: &Q) -> Option<Pin<&mut T>>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+        A::Pointer: UniquePointer<T>,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.search::<A, Q, C>(key, &self_.compare).ok()?;
+        Some(Pin::new(unsafe { &mut *node.as_ptr() }))
+    }
+
+    /// Links `item` as a leaf, rebuilding the subtree of a scapegoat above it
+    /// if the leaf ended up deeper than the alpha bound allows.
+    pub fn insert(self: Pin<&mut Self>, item: A::Pointer) -> Option<A::Pointer> {
+        let item = item.into_raw();
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        self_.link.assert_unlinked::<A>(item);
+        let key = A::get_key(unsafe { item.as_ref() });
+        match self_.link.search::<A, A::Key, C>(key, &self_.compare) {
+            Ok(_) => Some(unsafe { A::Pointer::from_raw(item) }),
+            Err(slot) => {
+                self_.link.link_leaf::<A>(&slot, item);
+                self_.len += 1;
+                self_.max_len = self_.max_len.max(self_.len);
+                if Self::depth_of(item) > Self::depth_limit(self_.len) {
+                    self_.rebuild_above(item);
+                }
+                None
+            }
+        }
+    }
+
+    pub fn remove<Q>(self: Pin<&mut Self>, key: &Q) -> Option<A::Pointer>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.search::<A, Q, C>(key, &self_.compare).ok()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn range<Q, R>(self: Pin<&Self>, range: R) -> Range<'_, T, A, P>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let self_ = Pin::get_ref(self);
+        self_.link.range::<A, Q, C, R>(range, &self_.compare)
+    }
+}
+
+impl<T, A, P, C> SGTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    /// The deepest a node may lie in a tree of `len` nodes: log base 1/alpha of `len`.
+    fn depth_limit(len: usize) -> usize {
+        let base = ALPHA.1 as f64 / ALPHA.0 as f64;
+        (len as f64).log(base).floor() as usize
+    }
+
+    fn depth_of(mut node: NonNull<T>) -> usize {
+        let mut depth = 0;
+        while let Some(top) = Link::of::<A>(node).top() {
+            node = top;
+            depth += 1;
+        }
+        depth
+    }
+
+    fn count(node: NonNull<T>) -> usize {
+        let last = Link::last_of::<A>(node);
+        let mut item = Link::first_of::<A>(node);
+        let mut len = 1;
+        while item != last {
+            item = Link::next_of::<A>(item).unwrap();
+            len += 1;
+        }
+        len
+    }
+
+    /// Rebuilds the subtree of the lowest ancestor of `node` whose child is
+    /// heavier than alpha times the ancestor itself.
+    fn rebuild_above(&mut self, mut node: NonNull<T>) {
+        let mut len = 1;
+        while let Some(top) = Link::of::<A>(node).top() {
+            let top_link = Link::of::<A>(top);
+            let sibling = if top_link.left() == Some(node) {
+                top_link.right()
+            } else {
+                top_link.left()
+            };
+            let top_len = len + 1 + sibling.map_or(0, Self::count);
+            if len * ALPHA.1 > top_len * ALPHA.0 {
+                self.rebuild(top);
+                return;
+            }
+            node = top;
+            len = top_len;
+        }
+    }
+
+    /// Balances the subtree of `node` in place, without extra space.
+    fn rebuild(&mut self, node: NonNull<T>) {
+        let top = Link::of::<A>(node).top();
+        let is_left = top.is_some_and(|top| Link::of::<A>(top).left() == Some(node));
+        // the subtree is balanced below a header of its own, then hung back
+        let mut sub = Link::new();
+        self.link.replace_child::<A>(top, node, None);
+        sub.hang::<A>(None, node, true);
+        sub.rebalance::<A>();
+        self.link.hang::<A>(top, sub.top().unwrap(), is_left);
+    }
+
+    /// Unlinks `node`, rebuilding the whole tree once it has shrunk below
+    /// alpha times its size at the last rebuild.
+    fn unlink(&mut self, node: NonNull<T>) -> A::Pointer {
+        self.link.unlink_node::<A>(node);
+        self.len -= 1;
+        if self.len * ALPHA.1 < self.max_len * ALPHA.0 {
+            self.link.rebalance::<A>();
+            self.max_len = self.len;
+        }
+        unsafe { A::Pointer::from_raw(node) }
+    }
+
+    pub fn pop_front(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.left()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn pop_back(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.right()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn front(self: Pin<&Self>) -> Option<&T> {
+        Pin::get_ref(self).link.front()
+    }
+
+    pub fn front_mut(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.front_mut()
+    }
+
+    pub fn back(self: Pin<&Self>) -> Option<&T> {
+        Pin::get_ref(self).link.back()
+    }
+
+    pub fn back_mut(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.back_mut()
+    }
+
+    pub fn iter(self: Pin<&Self>) -> Iter<'_, T, A, P> {
+        Pin::get_ref(self).link.iter()
+    }
+
+    pub fn iter_mut(self: Pin<&mut Self>) -> IterMut<'_, T, A, P>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.iter_mut()
+    }
+
+    pub fn len(self: Pin<&Self>) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(self: Pin<&Self>) -> bool {
+        self.len == 0
+    }
+
+    /// Unlinks every element, releasing the owned ones.
+    pub fn clear(self: Pin<&mut Self>) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        self_.link.clear::<A>();
+        self_.len = 0;
+        self_.max_len = 0;
+    }
+}
+
+impl<T, A, P, C> Default for SGTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>> + Default,
+    C: Default,
+{
+    fn default() -> Self {
+        Self::with_comparator(A::default(), C::default())
+    }
+}
+
+impl<T, A, P, C> Drop for SGTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn drop(&mut self) {
+        if mem::needs_drop::<A::Pointer>() {
+            unsafe { Pin::new_unchecked(self) }.clear();
+        }
+    }
+}
+
+impl<T, A, P, C> Unpin for SGTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T> + Unpin,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::adapter::NumerateSize;
+    use crate::tree_test::{self, tree_fixture};
+    use std::ops::Bound;
+
+    tree_fixture!(NumerateSize);
+
+    type Tree = SGTree<X, XLink>;
+
+    fn check(node: Option<NonNull<X>>, top: Option<NonNull<X>>) -> usize {
+        tree_test::check::<X, XLink, _>(node, top, &mut |_| {})
+    }
+
+    /// Checks the links and that no node lies deeper than the alpha bound.
+    fn check_tree(tree: Pin<&Tree>) {
+        let height = check(tree.link.top(), None);
+        assert!(height <= Tree::depth_limit(tree.len()) + 1);
+    }
+
+    #[test]
+    fn test_link_size() {
+        assert_eq!(mem::size_of::<Link<X>>(), 3 * mem::size_of::<usize>());
+    }
+
+    #[test]
+    fn test_insert_in_order() {
+        let mut tree = Box::pin(SGTree::new(XLink));
+        for i in 0..1000 {
+            assert!(tree.as_mut().insert(X::new(i)).is_none());
+            check_tree(tree.as_ref());
+        }
+        assert_eq!(tree.as_ref().len(), 1000);
+        assert_eq!(data(tree.as_ref()), (0..1000).collect::<Vec<_>>());
+        assert_eq!(tree.as_ref().front().unwrap().data, 0);
+        assert_eq!(tree.as_ref().back().unwrap().data, 999);
+
+        let item = tree.as_mut().insert(X::new(500)).unwrap();
+        assert_eq!(item.data, 500);
+        assert_eq!(tree.as_ref().len(), 1000);
+    }
+
+    #[test]
+    fn test_get_remove() {
+        let mut tree = Box::pin(SGTree::new(XLink));
+        for i in (0..100).rev() {
+            tree.as_mut().insert(X::new(i * 2));
+        }
+        assert_eq!(tree.as_ref().get(&42).unwrap().data, 42);
+        assert!(tree.as_ref().get(&43).is_none());
+        assert_eq!(SGTree::get_mut(tree.as_mut(), &0).unwrap().data, 0);
+
+        for i in 0..80 {
+            assert_eq!(tree.as_mut().remove(&(i * 2)).unwrap().data, i * 2);
+            check_tree(tree.as_ref());
+        }
+        assert!(tree.as_mut().remove(&0).is_none());
+        assert_eq!(tree.as_ref().len(), 20);
+        assert_eq!(tree.as_ref().front().unwrap().data, 160);
+
+        assert_eq!(tree.as_mut().pop_front().unwrap().data, 160);
+        assert_eq!(tree.as_mut().pop_back().unwrap().data, 198);
+        check_tree(tree.as_ref());
+        assert_eq!(tree.as_ref().len(), 18);
+        tree.as_mut().clear();
+        assert!(tree.as_ref().is_empty());
+        assert!(tree.as_mut().pop_back().is_none());
+    }
+
+    #[test]
+    fn test_range() {
+        let mut tree = Box::pin(SGTree::new(XLink));
+        for i in [5, 3, 8, 1, 4, 7, 9, 2, 6] {
+            tree.as_mut().insert(X::new(i));
+        }
+        assert_eq!(
+            tree.as_ref()
+                .range(3..6)
+                .map(|x| x.data)
+                .collect::<Vec<_>>(),
+            [3, 4, 5]
+        );
+        assert_eq!(
+            tree.as_ref()
+                .range::<i32, _>((Bound::Excluded(7), Bound::Unbounded))
+                .rev()
+                .map(|x| x.data)
+                .collect::<Vec<_>>(),
+            [9, 8]
+        );
+    }
+}
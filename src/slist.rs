@@ -1,27 +1,76 @@
 use crate::adapter::{LinkAdapter, Size};
-use crate::ptr::{NonNullPtr, Pointer};
+use crate::ptr::{NonNullPtr, OwningPointer, Pointer, UniquePointer};
+use std::cell::Cell;
 use std::cmp;
 use std::fmt;
 use std::fmt::Formatter;
 use std::marker::PhantomData;
+use std::mem;
 use std::pin::Pin;
 use std::ptr::NonNull;
 
+#[repr(C)]
 pub struct Link<T, P = NonNull<T>> {
-    next_ptr: Option<Pin<NonNullPtr<T, P>>>,
+    next_ptr: Cell<Option<Pin<NonNullPtr<T, P>>>>,
 }
 
 impl<T, P> Link<T, P> {
     pub const fn new() -> Self {
-        Self { next_ptr: None }
+        Self {
+            next_ptr: Cell::new(None),
+        }
     }
 
+    /// Whether the element has a successor in a list, which the last element
+    /// of a list does not.
     pub const fn is_linked(&self) -> bool {
-        self.next_ptr.is_some()
+        unsafe { (*self.next_ptr.as_ptr()).is_some() }
+    }
+
+    fn unlink(&self) {
+        self.next_ptr.set(None);
+    }
+}
+
+impl<T, P> Link<T, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+{
+    fn of<'a, A>(node: NonNull<T>) -> &'a Self
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        A::link_ref(unsafe { &*node.as_ptr() })
+    }
+
+    fn next(&self) -> Option<NonNull<T>> {
+        NonNullPtr::to_raw(&self.next_ptr)
     }
 
-    fn unlink(&mut self) {
-        self.next_ptr = None;
+    /// Panics if `item` is already in a list. The last element has no
+    /// successor and looks unlinked, so the front of this list is checked
+    /// too; the last element of a longer list still goes unnoticed.
+    fn assert_unlinked<A>(&self, item: NonNull<T>)
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        assert!(
+            !Self::of::<A>(item).is_linked() && self.next() != Some(item),
+            "the element is already linked"
+        );
+    }
+
+    /// Returns the link of `node`, where `None` stands for the header.
+    fn at<A>(&self, node: Option<NonNull<T>>) -> &Self
+    where
+        A: LinkAdapter<T, Link = Link<T, P>>,
+    {
+        if let Some(node) = node {
+            Self::of::<A>(node)
+        } else {
+            self
+        }
     }
 }
 
@@ -59,7 +108,7 @@ where
 {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "{{ next: ")?;
-        if let Some(next) = &self.next_ptr {
+        if let Some(next) = unsafe { &*self.next_ptr.as_ptr() } {
             write!(f, "{:?}", next)?;
         } else {
             write!(f, "0x0")?;
@@ -82,18 +131,14 @@ where
     type Item = Pin<&'a T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let link = unsafe { &*self.link };
-        if let Some(item) = &link.next_ptr {
-            self.link = A::link_ref(item);
-            Some(item.as_ref())
-        } else {
-            None
-        }
+        let item = unsafe { &*self.link }.next()?;
+        self.link = Link::of::<A>(item);
+        Some(Pin::new(unsafe { &*item.as_ptr() }))
     }
 }
 
 pub struct IterMut<'a, T, A, P> {
-    link: *mut Link<T, P>,
+    link: *const Link<T, P>,
     _marker: PhantomData<&'a A>,
 }
 
@@ -106,19 +151,17 @@ where
     type Item = Pin<&'a mut T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let link = unsafe { &mut *self.link };
-        if let Some(item) = &mut link.next_ptr {
-            self.link = A::link_mut(item);
-            Some(item.as_mut())
-        } else {
-            None
-        }
+        let item = unsafe { &*self.link }.next()?;
+        self.link = Link::of::<A>(item);
+        Some(Pin::new(unsafe { &mut *item.as_ptr() }))
     }
 }
 
 pub struct IntoIter<'a, T, A, P>
 where
-    A: LinkAdapter<T>,
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
 {
     item: Pin<&'a mut SinglyLinkedList<T, A, P>>,
 }
@@ -129,17 +172,136 @@ where
     P: Pointer<T>,
     A: LinkAdapter<T, Link = Link<T, P>>,
 {
-    type Item = NonNull<T>;
+    type Item = A::Pointer;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.item.as_mut().pop_front()
     }
 }
 
+/// A forward-only position in a list; `None` is the ghost position before the first element.
+pub struct CursorMut<'a, T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    current: Option<NonNull<T>>,
+    list: Pin<&'a mut SinglyLinkedList<T, A, P>>,
+}
+
+impl<'a, T, A, P> CursorMut<'a, T, A, P>
+where
+    T: Unpin + 'a,
+    P: Pointer<T> + 'a,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    /// Moves to the next element, or from the last one back to the ghost position.
+    pub fn move_next(&mut self) {
+        self.current = self.next();
+    }
+
+    pub fn is_ghost(&self) -> bool {
+        self.current.is_none()
+    }
+
+    pub fn current(&mut self) -> Option<Pin<&mut T>>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        self.current
+            .map(|node| Pin::new(unsafe { &mut *node.as_ptr() }))
+    }
+
+    pub fn peek_next(&mut self) -> Option<Pin<&mut T>>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        self.next()
+            .map(|node| Pin::new(unsafe { &mut *node.as_ptr() }))
+    }
+
+    pub fn insert_after(&mut self, item: A::Pointer) {
+        let item = item.into_raw();
+        let list = unsafe { self.list.as_mut().get_unchecked_mut() };
+        list.link.assert_unlinked::<A>(item);
+        let next = list.link.at::<A>(self.current).next();
+        let item_link = Link::of::<A>(item);
+        NonNullPtr::assign_raw(&item_link.next_ptr, next);
+        NonNullPtr::assign(&list.link.at::<A>(self.current).next_ptr, item);
+        list.size.incr();
+    }
+
+    pub fn remove_after(&mut self) -> Option<A::Pointer> {
+        let list = unsafe { self.list.as_mut().get_unchecked_mut() };
+        let node = list.link.at::<A>(self.current).next()?;
+        let node_link = Link::of::<A>(node);
+        let next = node_link.next();
+        node_link.unlink();
+        NonNullPtr::assign_raw(&list.link.at::<A>(self.current).next_ptr, next);
+        list.size.decr();
+        Some(unsafe { A::Pointer::from_raw(node) })
+    }
+
+    /// Moves every element after the cursor to the front of `other`; linear in the number moved.
+    pub fn split_after(&mut self, other: Pin<&mut SinglyLinkedList<T, A, P>>) {
+        let other = unsafe { Pin::get_unchecked_mut(other) };
+        let list = unsafe { self.list.as_mut().get_unchecked_mut() };
+        let link = list.link.at::<A>(self.current);
+        let Some(head) = link.next() else {
+            return;
+        };
+        link.unlink();
+        let mut tail = head;
+        loop {
+            list.size.decr();
+            other.size.incr();
+            match Link::of::<A>(tail).next() {
+                Some(next) => tail = next,
+                None => break,
+            }
+        }
+        let next = other.link.next();
+        NonNullPtr::assign_raw(&Link::of::<A>(tail).next_ptr, next);
+        NonNullPtr::assign(&other.link.next_ptr, head);
+    }
+
+    /// Moves every element of `other` after the cursor; linear in the length of `other`.
+    pub fn splice_after(&mut self, other: Pin<&mut SinglyLinkedList<T, A, P>>) {
+        let other = unsafe { Pin::get_unchecked_mut(other) };
+        let Some(head) = other.link.next() else {
+            return;
+        };
+        let list = unsafe { self.list.as_mut().get_unchecked_mut() };
+        let mut tail = head;
+        loop {
+            other.size.decr();
+            list.size.incr();
+            match Link::of::<A>(tail).next() {
+                Some(next) => tail = next,
+                None => break,
+            }
+        }
+        other.link.unlink();
+        let next = list.link.at::<A>(self.current).next();
+        NonNullPtr::assign_raw(&Link::of::<A>(tail).next_ptr, next);
+        NonNullPtr::assign(&list.link.at::<A>(self.current).next_ptr, head);
+    }
+
+    fn next(&self) -> Option<NonNull<T>> {
+        match self.current {
+            Some(node) => Link::of::<A>(node).next(),
+            None => self.list.link.next(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct SinglyLinkedList<T, A, P = NonNull<T>>
 where
-    A: LinkAdapter<T>,
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
 {
     size: A::Size,
     link: Link<T, P>,
@@ -147,7 +309,9 @@ where
 
 impl<T, A, P> SinglyLinkedList<T, A, P>
 where
-    A: LinkAdapter<T>,
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
 {
     pub fn new(_: A) -> Self {
         Self {
@@ -163,76 +327,73 @@ where
     P: Pointer<T>,
     A: LinkAdapter<T, Link = Link<T, P>>,
 {
-    pub fn push_front(self: Pin<&mut Self>, mut item: NonNull<T>) {
-        let item_link = A::link_mut(unsafe { item.as_mut() });
-        debug_assert_eq!(item_link.is_linked(), false);
-
-        let self_ = Pin::into_inner(self);
-        let head_ptr = &mut self_.link.next_ptr;
-        if let Some(head) = head_ptr {
-            NonNullPtr::assign_pin(&mut item_link.next_ptr, head);
-        }
-        NonNullPtr::assign(head_ptr, item);
+    pub fn push_front(self: Pin<&mut Self>, item: A::Pointer) {
+        let item = item.into_raw();
+        self.link.assert_unlinked::<A>(item);
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        NonNullPtr::assign_raw(&Link::of::<A>(item).next_ptr, self_.link.next());
+        NonNullPtr::assign(&self_.link.next_ptr, item);
         self_.size.incr();
     }
 
-    pub fn pop_front(self: Pin<&mut Self>) -> Option<NonNull<T>> {
-        let self_ = Pin::into_inner(self);
-        let head_ptr = &mut self_.link.next_ptr;
-        if let Some(head) = head_ptr {
-            let mut head = NonNull::from(head.as_mut().get_mut());
-            let head_link = A::link_mut(unsafe { head.as_mut() });
-            if let Some(next) = &mut head_link.next_ptr {
-                NonNullPtr::assign_pin(head_ptr, next);
-            } else {
-                self_.link.unlink();
-            }
-            head_link.unlink();
-            self_.size.decr();
-            Some(head)
-        } else {
-            None
-        }
+    pub fn pop_front(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let head = self_.link.next()?;
+        let head_link = Link::of::<A>(head);
+        NonNullPtr::assign_raw(&self_.link.next_ptr, head_link.next());
+        head_link.unlink();
+        self_.size.decr();
+        Some(unsafe { A::Pointer::from_raw(head) })
     }
 
     pub fn front(self: Pin<&Self>) -> Option<Pin<&T>> {
-        let self_ = Pin::into_inner(self);
-        if let Some(first) = &self_.link.next_ptr {
-            Some(first.as_ref())
-        } else {
-            None
-        }
+        let first = Pin::get_ref(self).link.next()?;
+        Some(Pin::new(unsafe { &*first.as_ptr() }))
     }
 
-    pub fn front_mut(self: Pin<&mut Self>) -> Option<Pin<&mut T>> {
-        let self_ = Pin::into_inner(self);
-        if let Some(first) = &mut self_.link.next_ptr {
-            Some(first.as_mut())
-        } else {
-            None
-        }
+    pub fn front_mut(self: Pin<&mut Self>) -> Option<Pin<&mut T>>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        let first = self.link.next()?;
+        Some(Pin::new(unsafe { &mut *first.as_ptr() }))
     }
 
-    pub const fn iter(self: Pin<&Self>) -> Iter<T, A, P> {
-        let self_ = Pin::into_inner(self);
+    pub const fn iter(self: Pin<&Self>) -> Iter<'_, T, A, P> {
+        let self_ = Pin::get_ref(self);
         Iter {
             link: &self_.link,
             _marker: PhantomData,
         }
     }
 
-    pub fn iter_mut(self: Pin<&mut Self>) -> IterMut<T, A, P> {
-        let self_ = Pin::into_inner(self);
+    pub fn iter_mut(self: Pin<&mut Self>) -> IterMut<'_, T, A, P>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
         IterMut {
-            link: &mut self_.link,
+            link: &self_.link,
             _marker: PhantomData,
         }
     }
 
-    pub const fn into_iter(self: Pin<&mut Self>) -> IntoIter<T, A, P> {
+    pub const fn into_iter(self: Pin<&mut Self>) -> IntoIter<'_, T, A, P> {
         IntoIter { item: self }
     }
 
+    pub fn cursor_mut(self: Pin<&mut Self>) -> CursorMut<'_, T, A, P> {
+        CursorMut {
+            current: None,
+            list: self,
+        }
+    }
+
+    /// Unlinks every element, releasing the owned ones.
+    pub fn clear(mut self: Pin<&mut Self>) {
+        while self.as_mut().pop_front().is_some() {}
+    }
+
     pub fn is_empty(self: Pin<&Self>) -> bool {
         self.size.is_empty(self.iter())
     }
@@ -263,17 +424,33 @@ where
 
 impl<T, A, P> Default for SinglyLinkedList<T, A, P>
 where
-    A: LinkAdapter<T> + Default,
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>> + Default,
 {
     fn default() -> Self {
         Self::new(A::default())
     }
 }
 
+impl<T, A, P> Drop for SinglyLinkedList<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn drop(&mut self) {
+        if mem::needs_drop::<A::Pointer>() {
+            unsafe { Pin::new_unchecked(self) }.clear();
+        }
+    }
+}
+
 impl<T, A, P> Unpin for SinglyLinkedList<T, A, P>
 where
     T: Unpin,
-    A: LinkAdapter<T>,
+    P: Pointer<T> + Unpin,
+    A: LinkAdapter<T, Link = Link<T, P>>,
 {
 }
 
@@ -326,6 +503,7 @@ mod test {
     impl LinkAdapter<X> for XLink {
         type Link = Link<X>;
         type Size = NumerateSize;
+        type Pointer = NonNull<X>;
 
         fn link_ref(data: &X) -> &Self::Link {
             &data.link
@@ -408,4 +586,174 @@ mod test {
         assert_eq!(lst.as_ref().len(), 0);
         assert_eq!(lst.as_mut().pop_front(), None);
     }
+
+    fn collect(lst: Pin<&SinglyLinkedList<X, XLink>>) -> Vec<i32> {
+        lst.iter().map(|x| x.data).collect()
+    }
+
+    #[test]
+    fn test_cursor_mut() {
+        let mut lst = Box::pin(SinglyLinkedList::new(XLink));
+        let mut cur = lst.as_mut().cursor_mut();
+        assert!(cur.is_ghost());
+        assert_eq!(cur.remove_after(), None);
+        cur.insert_after(X::new(3));
+        cur.insert_after(X::new(1));
+        cur.move_next();
+        // [(1),3]
+        assert_eq!(cur.current().unwrap().data, 1);
+        cur.insert_after(X::new(2));
+        assert_eq!(cur.peek_next().unwrap().data, 2);
+        cur.move_next();
+        cur.move_next();
+        // [1,2,(3)]
+        assert_eq!(cur.peek_next(), None);
+        cur.insert_after(X::new(4));
+        assert_eq!(collect(lst.as_ref()), [1, 2, 3, 4]);
+
+        let mut cur = lst.as_mut().cursor_mut();
+        cur.move_next();
+        let item = X::from(cur.remove_after()).unwrap();
+        assert_eq!(item.data, 2);
+        cur.move_next();
+        cur.move_next();
+        // [1,3,(4)]
+        assert_eq!(cur.remove_after(), None);
+        cur.move_next();
+        assert!(cur.is_ghost());
+        let item = X::from(cur.remove_after()).unwrap();
+        assert_eq!(item.data, 1);
+        assert_eq!(collect(lst.as_ref()), [3, 4]);
+        assert_eq!(lst.as_ref().len(), 2);
+        while X::from(lst.as_mut().pop_front()).is_some() {}
+    }
+
+    #[test]
+    fn test_cursor_split_splice() {
+        let mut lst = Box::pin(SinglyLinkedList::new(XLink));
+        for i in (1..=5).rev() {
+            lst.as_mut().push_front(X::new(i));
+        }
+        // [1,2,3,4,5]
+        let mut cur = lst.as_mut().cursor_mut();
+        cur.move_next();
+        cur.move_next();
+        let mut other = Box::pin(SinglyLinkedList::new(XLink));
+        cur.split_after(other.as_mut());
+        assert_eq!(collect(lst.as_ref()), [1, 2]);
+        assert_eq!(collect(other.as_ref()), [3, 4, 5]);
+
+        let mut cur = lst.as_mut().cursor_mut();
+        cur.move_next();
+        cur.splice_after(other.as_mut());
+        assert!(other.as_ref().is_empty());
+        assert_eq!(collect(lst.as_ref()), [1, 3, 4, 5, 2]);
+
+        let mut cur = lst.as_mut().cursor_mut();
+        cur.splice_after(other.as_mut());
+        cur.split_after(other.as_mut());
+        assert!(lst.as_ref().is_empty());
+        assert_eq!(collect(other.as_ref()), [1, 3, 4, 5, 2]);
+
+        // the split elements go in front of those already in `other`
+        lst.as_mut().push_front(X::new(6));
+        lst.as_mut().push_front(X::new(7));
+        let mut cur = lst.as_mut().cursor_mut();
+        cur.split_after(other.as_mut());
+        assert!(lst.as_ref().is_empty());
+        assert_eq!(collect(other.as_ref()), [7, 6, 1, 3, 4, 5, 2]);
+        let mut cur = other.as_mut().cursor_mut();
+        cur.move_next();
+        cur.move_next();
+        cur.split_after(lst.as_mut());
+        assert_eq!(collect(other.as_ref()), [7, 6]);
+        assert_eq!(collect(lst.as_ref()), [1, 3, 4, 5, 2]);
+        while X::from(other.as_mut().pop_front()).is_some() {}
+
+        let mut cur = lst.as_mut().cursor_mut();
+        cur.splice_after(other.as_mut());
+        assert!(other.as_ref().is_empty());
+        assert_eq!(collect(lst.as_ref()), [1, 3, 4, 5, 2]);
+        while X::from(lst.as_mut().pop_front()).is_some() {}
+    }
+
+    #[test]
+    fn test_cursor_literate_size() {
+        #[derive(Debug, Default)]
+        struct XSizedLink;
+
+        impl LinkAdapter<X> for XSizedLink {
+            type Link = Link<X>;
+            type Size = crate::adapter::LiterateSize;
+            type Pointer = NonNull<X>;
+
+            fn link_ref(data: &X) -> &Self::Link {
+                &data.link
+            }
+
+            fn link_mut(data: &mut X) -> &mut Self::Link {
+                &mut data.link
+            }
+        }
+
+        let mut lst = Box::pin(SinglyLinkedList::new(XSizedLink));
+        let mut cur = lst.as_mut().cursor_mut();
+        for i in 1..=4 {
+            cur.insert_after(X::new(i));
+        }
+        cur.move_next();
+        let mut other = Box::pin(SinglyLinkedList::new(XSizedLink));
+        cur.split_after(other.as_mut());
+        assert_eq!(lst.as_ref().len(), 1);
+        assert_eq!(other.as_ref().len(), 3);
+        X::from(lst.as_mut().cursor_mut().remove_after()).unwrap();
+        assert!(lst.as_ref().is_empty());
+        lst.as_mut().cursor_mut().splice_after(other.as_mut());
+        assert_eq!(lst.as_ref().len(), 3);
+        assert_eq!(other.as_ref().len(), 0);
+        while X::from(lst.as_mut().pop_front()).is_some() {}
+    }
+
+    #[derive(Debug)]
+    struct XBoxLink;
+
+    impl LinkAdapter<X> for XBoxLink {
+        type Link = Link<X>;
+        type Size = NumerateSize;
+        type Pointer = Box<X>;
+
+        fn link_ref(data: &X) -> &Self::Link {
+            &data.link
+        }
+
+        fn link_mut(data: &mut X) -> &mut Self::Link {
+            &mut data.link
+        }
+    }
+
+    #[test]
+    fn test_owning() {
+        let mut lst = Box::pin(SinglyLinkedList::new(XBoxLink));
+        for data in 1..=4 {
+            lst.as_mut().push_front(Box::new(X {
+                data,
+                link: Link::new(),
+            }));
+        }
+        // [4,3,2,1]
+        let item = lst.as_mut().pop_front().unwrap();
+        assert_eq!(item.data, 4);
+        let mut cur = lst.as_mut().cursor_mut();
+        cur.move_next();
+        let item = cur.remove_after().unwrap();
+        assert_eq!(item.data, 2);
+        cur.insert_after(item);
+        assert_eq!(lst.as_ref().len(), 3);
+        lst.as_mut().clear();
+        assert!(lst.as_ref().is_empty());
+        lst.as_mut().push_front(Box::new(X {
+            data: 5,
+            link: Link::new(),
+        }));
+    }
 }
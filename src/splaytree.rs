@@ -0,0 +1,522 @@
+use crate::adapter::{Compare, KeyAdapter, LinkAdapter, NaturalOrder, Size};
+use crate::ptr::{OwningPointer, Pointer, UniquePointer};
+use std::borrow::Borrow;
+use std::cmp;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+use std::pin::Pin;
+use std::ptr::NonNull;
+
+pub use crate::bintree::{Iter, IterMut, Link, Range};
+
+/// A self-adjusting search tree: every lookup, insert and removal splays the
+/// node it touched to the root, so recently used keys are the cheapest to reach.
+pub struct SplayTree<T, A, P = NonNull<T>, C = NaturalOrder>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    size: A::Size,
+    link: Link<T, P>,
+    compare: C,
+}
+
+impl<T, A, P> SplayTree<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    pub fn new(adapter: A) -> Self {
+        Self::with_comparator(adapter, NaturalOrder)
+    }
+}
+
+impl<T, A, P, C> SplayTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    /// Creates a tree that orders its keys by `compare` instead of `Ord`.
+    ///
+    /// A closure compares `A::Key` only, so lookups then take `&A::Key`
+    /// rather than a borrowed form of it; see [`Compare`].
+    pub fn with_comparator(_: A, compare: C) -> Self {
+        Self {
+            size: Default::default(),
+            link: Link::new(),
+            compare,
+        }
+    }
+}
+
+impl<T, A, P, C> SplayTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    C: Compare<A::Key>,
+{
+    /// Finds the element for `key` and splays it to the root; a miss splays
+    /// the last node visited instead.
+    pub fn get<Q>(self: Pin<&mut Self>, key: &Q) -> Option<Pin<&mut T>>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+        A::Pointer: UniquePointer<T>,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        match self_.link.search::<A, Q, C>(key, &self_.compare) {
+            Ok(node) => {
+                self_.splay(node);
+                Some(Pin::new(unsafe { &mut *node.as_ptr() }))
+            }
+            Err(slot) => {
+                if let Some(top) = slot.top {
+                    self_.splay(top);
+                }
+                None
+            }
+        }
+    }
+
+    /// Links `item` at the root, or hands it back if its key is already present.
+    pub fn insert(self: Pin<&mut Self>, item: A::Pointer) -> Option<A::Pointer> {
+        let item = item.into_raw();
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        self_.link.assert_unlinked::<A>(item);
+        let key = A::get_key(unsafe { item.as_ref() });
+        match self_.link.search::<A, A::Key, C>(key, &self_.compare) {
+            Ok(node) => {
+                self_.splay(node);
+                Some(unsafe { A::Pointer::from_raw(item) })
+            }
+            Err(slot) => {
+                self_.link.link_leaf::<A>(&slot, item);
+                self_.size.incr();
+                self_.splay(item);
+                None
+            }
+        }
+    }
+
+    pub fn remove<Q>(self: Pin<&mut Self>, key: &Q) -> Option<A::Pointer>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        match self_.link.search::<A, Q, C>(key, &self_.compare) {
+            Ok(node) => {
+                self_.splay(node);
+                Some(self_.unlink(node))
+            }
+            Err(slot) => {
+                if let Some(top) = slot.top {
+                    self_.splay(top);
+                }
+                None
+            }
+        }
+    }
+
+    /// Iterates over the elements whose keys lie in `range`, without splaying.
+    pub fn range<Q, R>(self: Pin<&Self>, range: R) -> Range<'_, T, A, P>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let self_ = Pin::get_ref(self);
+        self_.link.range::<A, Q, C, R>(range, &self_.compare)
+    }
+
+    /// Moves every element whose key is not less than `key` into the empty tree `other`.
+    ///
+    /// The first moved element is splayed to the root, so the cut is a single
+    /// link. A `LiterateSize` has to count the moved elements, which makes the
+    /// split O(k) in their number; a `NumerateSize` skips that walk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is not empty.
+    pub fn split_off<Q>(self: Pin<&mut Self>, key: &Q, other: Pin<&mut Self>)
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let other = unsafe { Pin::get_unchecked_mut(other) };
+        assert!(
+            other.link.top().is_none(),
+            "the tree to split into is not empty"
+        );
+        let Some(node) = self_
+            .link
+            .lower_node::<A, Q, C>(Bound::Included(key), &self_.compare)
+        else {
+            return;
+        };
+        let (first, last) = (self_.link.left(), self_.link.right());
+        let prev = Link::prev_of::<A>(node);
+        Link::move_size::<A>(&mut self_.size, &mut other.size, Some(node));
+
+        self_.splay(node);
+        let left = Link::of::<A>(node).left();
+        self_.link.unlink();
+        if let Some(left) = left {
+            self_.link.replace_child::<A>(Some(node), left, None);
+            self_.link.hang::<A>(None, left, true);
+            self_.link.set_ends(first, prev);
+        }
+        other.link.hang::<A>(None, node, true);
+        other.link.set_ends(Some(node), last);
+    }
+
+    /// Moves every element of `other` into this tree.
+    ///
+    /// The keys of `other` must all lie above or all lie below those of this
+    /// tree: the nearest end is splayed to the root and `other` is hung below
+    /// it. The sizes move over in one step, without visiting the elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key ranges of the two trees overlap.
+    pub fn append(self: Pin<&mut Self>, other: Pin<&mut Self>) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let other = unsafe { Pin::get_unchecked_mut(other) };
+        let (Some(other_top), Some(other_first), Some(other_last)) =
+            (other.link.top(), other.link.left(), other.link.right())
+        else {
+            return;
+        };
+        let key = |node: NonNull<T>| A::get_key(unsafe { node.as_ref() });
+        let ends = match (self_.link.left(), self_.link.right()) {
+            (Some(first), Some(last)) => {
+                let is_less = |a, b| self_.compare.compare(key(a), key(b)) == cmp::Ordering::Less;
+                if is_less(last, other_first) {
+                    Some((last, first, other_last, false))
+                } else if is_less(other_last, first) {
+                    Some((first, other_first, last, true))
+                } else {
+                    panic!("the keys of the appended tree overlap");
+                }
+            }
+            _ => None,
+        };
+
+        other
+            .link
+            .move_all_size::<A>(&mut other.size, &mut self_.size);
+        other.link.unlink();
+        match ends {
+            Some((top, first, last, is_left)) => {
+                self_.splay(top);
+                self_.link.hang::<A>(Some(top), other_top, is_left);
+                self_.link.set_ends(Some(first), Some(last));
+            }
+            None => {
+                self_.link.hang::<A>(None, other_top, true);
+                self_.link.set_ends(Some(other_first), Some(other_last));
+            }
+        }
+    }
+}
+
+impl<T, A, P, C> SplayTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    /// Rotates `node` up to the root, two levels at a time.
+    fn splay(&mut self, node: NonNull<T>) {
+        while let Some(top) = Link::of::<A>(node).top() {
+            let is_left = Link::of::<A>(top).left() == Some(node);
+            match Link::of::<A>(top).top() {
+                None => self.rotate_up(top, is_left),
+                Some(grand) => {
+                    let top_is_left = Link::of::<A>(grand).left() == Some(top);
+                    if is_left == top_is_left {
+                        // zig-zig: lift top first so node's path is halved
+                        self.rotate_up(grand, top_is_left);
+                        self.rotate_up(top, is_left);
+                    } else {
+                        // zig-zag: node climbs over both
+                        self.rotate_up(top, is_left);
+                        self.rotate_up(grand, top_is_left);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Lifts the left or right child of `node` into its place.
+    fn rotate_up(&mut self, node: NonNull<T>, is_left: bool) {
+        if is_left {
+            self.link.rotate_right::<A>(node);
+        } else {
+            self.link.rotate_left::<A>(node);
+        }
+    }
+
+    /// Unlinks `node` without splaying.
+    fn unlink(&mut self, node: NonNull<T>) -> A::Pointer {
+        self.link.unlink_node::<A>(node);
+        self.size.decr();
+        unsafe { A::Pointer::from_raw(node) }
+    }
+
+    pub fn pop_front(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.left()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn pop_back(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.right()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn front(self: Pin<&Self>) -> Option<&T> {
+        Pin::get_ref(self).link.front()
+    }
+
+    pub fn front_mut(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.front_mut()
+    }
+
+    pub fn back(self: Pin<&Self>) -> Option<&T> {
+        Pin::get_ref(self).link.back()
+    }
+
+    pub fn back_mut(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.back_mut()
+    }
+
+    pub fn iter(self: Pin<&Self>) -> Iter<'_, T, A, P> {
+        Pin::get_ref(self).link.iter()
+    }
+
+    pub fn iter_mut(self: Pin<&mut Self>) -> IterMut<'_, T, A, P>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.iter_mut()
+    }
+
+    pub fn len(self: Pin<&Self>) -> usize {
+        self.link.len::<A>(&self.size)
+    }
+
+    pub fn is_empty(self: Pin<&Self>) -> bool {
+        self.link.is_empty::<A>(&self.size)
+    }
+
+    /// Unlinks every element, releasing the owned ones.
+    pub fn clear(self: Pin<&mut Self>) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        self_.link.clear::<A>();
+        self_.size = Default::default();
+    }
+}
+
+impl<T, A, P, C> Default for SplayTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>> + Default,
+    C: Default,
+{
+    fn default() -> Self {
+        Self::with_comparator(A::default(), C::default())
+    }
+}
+
+impl<T, A, P, C> Drop for SplayTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn drop(&mut self) {
+        if mem::needs_drop::<A::Pointer>() {
+            unsafe { Pin::new_unchecked(self) }.clear();
+        }
+    }
+}
+
+impl<T, A, P, C> Unpin for SplayTree<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T> + Unpin,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::adapter::LiterateSize;
+    use crate::tree_test::{self, tree_fixture};
+
+    tree_fixture!(LiterateSize);
+
+    type Tree = SplayTree<X, XLink>;
+
+    fn root(tree: Pin<&Tree>) -> Option<i32> {
+        tree.link.top().map(|node| unsafe { node.as_ref() }.data)
+    }
+
+    fn check(node: Option<NonNull<X>>, top: Option<NonNull<X>>) {
+        tree_test::check::<X, XLink, _>(node, top, &mut |_| {});
+    }
+
+    fn filled(keys: impl IntoIterator<Item = i32>) -> Pin<Box<Tree>> {
+        let mut tree = Box::pin(SplayTree::new(XLink));
+        for i in keys {
+            assert!(tree.as_mut().insert(X::new(i)).is_none());
+        }
+        tree
+    }
+
+    #[test]
+    fn test_insert_splays() {
+        let mut tree = filled([4, 1, 6, 3, 7, 2, 5]);
+        assert_eq!(root(tree.as_ref()), Some(5));
+        assert_eq!(data(tree.as_ref()), [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(tree.as_ref().len(), 7);
+        check(tree.link.top(), None);
+
+        let item = tree.as_mut().insert(X::new(2)).unwrap();
+        assert_eq!(item.data, 2);
+        assert_eq!(root(tree.as_ref()), Some(2));
+        assert_eq!(tree.as_ref().len(), 7);
+        check(tree.link.top(), None);
+    }
+
+    #[test]
+    fn test_get_splays() {
+        let mut tree = filled(1..=7);
+        // inserting in order leaves a left-leaning path below 7
+        assert_eq!(root(tree.as_ref()), Some(7));
+
+        tree.as_mut().get(&1).unwrap().data = 1;
+        assert_eq!(root(tree.as_ref()), Some(1));
+        check(tree.link.top(), None);
+
+        assert_eq!(tree.as_mut().get(&4).unwrap().data, 4);
+        assert_eq!(root(tree.as_ref()), Some(4));
+        check(tree.link.top(), None);
+
+        assert!(tree.as_mut().get(&10).is_none());
+        assert_eq!(root(tree.as_ref()), Some(7));
+        check(tree.link.top(), None);
+        assert_eq!(data(tree.as_ref()), [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(tree.as_ref().front().unwrap().data, 1);
+        assert_eq!(tree.as_ref().back().unwrap().data, 7);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut tree = filled([4, 1, 6, 3, 7, 2, 5]);
+        assert_eq!(tree.as_mut().remove(&4).unwrap().data, 4);
+        assert!(tree.as_mut().remove(&4).is_none());
+        check(tree.link.top(), None);
+        assert_eq!(tree.as_mut().remove(&1).unwrap().data, 1);
+        assert_eq!(tree.as_mut().remove(&7).unwrap().data, 7);
+        check(tree.link.top(), None);
+        assert_eq!(data(tree.as_ref()), [2, 3, 5, 6]);
+        assert_eq!(tree.as_ref().front().unwrap().data, 2);
+        assert_eq!(tree.as_ref().back().unwrap().data, 6);
+        assert_eq!(tree.as_ref().len(), 4);
+
+        assert_eq!(tree.as_mut().pop_front().unwrap().data, 2);
+        assert_eq!(tree.as_mut().pop_back().unwrap().data, 6);
+        assert_eq!(data(tree.as_ref()), [3, 5]);
+        tree.as_mut().clear();
+        assert!(tree.as_ref().is_empty());
+        assert!(tree.as_mut().pop_front().is_none());
+    }
+
+    #[test]
+    fn test_range() {
+        let tree = filled([4, 1, 6, 3, 7, 2, 5]);
+        let range = |r: (Bound<i32>, Bound<i32>)| {
+            tree.as_ref()
+                .range::<i32, _>(r)
+                .map(|x| x.data)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(range((Bound::Included(2), Bound::Excluded(5))), [2, 3, 4]);
+        assert_eq!(range((Bound::Excluded(5), Bound::Unbounded)), [6, 7]);
+        assert_eq!(range((Bound::Included(8), Bound::Unbounded)), []);
+        assert_eq!(
+            tree.as_ref()
+                .range(3..=4)
+                .rev()
+                .map(|x| x.data)
+                .collect::<Vec<_>>(),
+            [4, 3]
+        );
+    }
+
+    #[test]
+    fn test_split_append() {
+        let mut tree = filled([4, 1, 6, 3, 7, 2, 5]);
+        let mut other = Box::pin(SplayTree::new(XLink));
+        tree.as_mut().split_off(&4, other.as_mut());
+        assert_eq!(data(tree.as_ref()), [1, 2, 3]);
+        assert_eq!(data(other.as_ref()), [4, 5, 6, 7]);
+        assert_eq!(root(other.as_ref()), Some(4));
+        assert_eq!((tree.as_ref().len(), other.as_ref().len()), (3, 4));
+        assert_eq!(tree.as_ref().back().unwrap().data, 3);
+        assert_eq!(other.as_ref().front().unwrap().data, 4);
+        assert_eq!(other.as_ref().back().unwrap().data, 7);
+        check(tree.link.top(), None);
+        check(other.link.top(), None);
+
+        let mut empty = Box::pin(SplayTree::new(XLink));
+        tree.as_mut().split_off(&10, empty.as_mut());
+        assert!(empty.as_ref().is_empty());
+        assert_eq!(data(tree.as_ref()), [1, 2, 3]);
+
+        other.as_mut().append(tree.as_mut());
+        assert!(tree.as_ref().is_empty());
+        assert_eq!(data(other.as_ref()), [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(other.as_ref().front().unwrap().data, 1);
+        assert_eq!(other.as_ref().len(), 7);
+        check(other.link.top(), None);
+
+        let mut high = filled([9, 8]);
+        other.as_mut().append(high.as_mut());
+        assert_eq!(data(other.as_ref()), [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(other.as_ref().back().unwrap().data, 9);
+        check(other.link.top(), None);
+
+        tree.as_mut().append(other.as_mut());
+        assert_eq!(data(tree.as_ref()), [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(tree.as_ref().len(), 9);
+    }
+
+    #[test]
+    #[should_panic(expected = "overlap")]
+    fn test_append_overlap() {
+        let mut tree = filled([1, 3]);
+        let mut other = filled([2]);
+        tree.as_mut().append(other.as_mut());
+    }
+}
@@ -0,0 +1,624 @@
+use crate::adapter::{Compare, KeyAdapter, LinkAdapter, NaturalOrder, Size};
+use crate::bintree::{self, AsTreeLink, TreeLink};
+use crate::ptr::{OwningPointer, Pointer, UniquePointer};
+use std::borrow::Borrow;
+use std::cell::Cell;
+use std::cmp;
+use std::fmt;
+use std::mem;
+use std::ops::RangeBounds;
+use std::pin::Pin;
+use std::ptr::NonNull;
+
+pub type Iter<'a, T, A, P> = bintree::Iter<'a, T, TreeLink<A>, P>;
+
+pub type IterMut<'a, T, A, P> = bintree::IterMut<'a, T, TreeLink<A>, P>;
+
+pub type Range<'a, T, A, P> = bintree::Range<'a, T, TreeLink<A>, P>;
+
+/// The seed a treap draws its priorities from unless given another.
+const DEFAULT_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// A `bintree::Link` that also carries the heap priority of its node.
+#[repr(C)]
+pub struct Link<T, P = NonNull<T>> {
+    tree: bintree::Link<T, P>,
+    priority: Cell<u32>,
+}
+
+impl<T, P> Link<T, P> {
+    pub const fn new() -> Self {
+        Self {
+            tree: bintree::Link::new(),
+            priority: Cell::new(0),
+        }
+    }
+
+    pub const fn is_linked(&self) -> bool {
+        self.tree.is_linked()
+    }
+}
+
+impl<T, P> Default for Link<T, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, P> Unpin for Link<T, P> where T: Unpin {}
+
+impl<T, P> cmp::PartialEq for Link<T, P> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<T, P> cmp::Eq for Link<T, P> {}
+
+impl<T, P> cmp::PartialOrd for Link<T, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, P> cmp::Ord for Link<T, P> {
+    fn cmp(&self, _: &Self) -> cmp::Ordering {
+        cmp::Ordering::Equal
+    }
+}
+
+impl<T, P> fmt::Debug for Link<T, P>
+where
+    P: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} #{}", self.tree, self.priority.get())
+    }
+}
+
+impl<T, P> AsTreeLink<T> for Link<T, P> {
+    type Pointer = P;
+
+    fn tree_ref<A>(data: &T) -> &bintree::Link<T, P>
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        &A::link_ref(data).tree
+    }
+
+    fn tree_mut<A>(data: &mut T) -> &mut bintree::Link<T, P>
+    where
+        A: LinkAdapter<T, Link = Self>,
+    {
+        &mut A::link_mut(data).tree
+    }
+}
+
+/// A xorshift generator; plenty to shuffle priorities, and reproducible from its seed.
+#[derive(Clone, Debug)]
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // zero is the one state xorshift never leaves
+        Self(if seed == 0 { DEFAULT_SEED } else { seed })
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+}
+
+/// A randomized search tree: ordered by key, and a max-heap by the random
+/// priority each element is given on insert, which keeps it balanced in
+/// expectation.
+pub struct Treap<T, A, P = NonNull<T>, C = NaturalOrder>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    size: A::Size,
+    link: bintree::Link<T, P>,
+    compare: C,
+    rng: XorShift,
+}
+
+impl<T, A, P> Treap<T, A, P>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    pub fn new(adapter: A) -> Self {
+        Self::with_seed(adapter, DEFAULT_SEED)
+    }
+
+    /// Creates a tree whose priorities are drawn from a generator seeded with `seed`.
+    pub fn with_seed(adapter: A, seed: u64) -> Self {
+        Self::with_comparator_and_seed(adapter, NaturalOrder, seed)
+    }
+}
+
+impl<T, A, P, C> Treap<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    /// Creates a tree that orders its keys by `compare` instead of `Ord`.
+    pub fn with_comparator(adapter: A, compare: C) -> Self {
+        Self::with_comparator_and_seed(adapter, compare, DEFAULT_SEED)
+    }
+
+    /// Creates a tree ordered by `compare` whose priorities are seeded with `seed`.
+    pub fn with_comparator_and_seed(_: A, compare: C, seed: u64) -> Self {
+        Self {
+            size: Default::default(),
+            link: bintree::Link::new(),
+            compare,
+            rng: XorShift::new(seed),
+        }
+    }
+}
+
+impl<T, A, P, C> Treap<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    C: Compare<A::Key>,
+{
+    pub fn get<Q>(self: Pin<&Self>, key: &Q) -> Option<Pin<&T>>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let self_ = Pin::get_ref(self);
+        let node = self_
+            .link
+            .search::<TreeLink<A>, Q, C>(key, &self_.compare)
+            .ok()?;
+        Some(Pin::new(unsafe { &*node.as_ptr() }))
+    }
+
+    pub fn get_mut<Q>(self: Pin<&mut Self>, key: &Q) -> Option<Pin<&mut T>>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+        A::Pointer: UniquePointer<T>,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_
+            .link
+            .search::<TreeLink<A>, Q, C>(key, &self_.compare)
+            .ok()?;
+        Some(Pin::new(unsafe { &mut *node.as_ptr() }))
+    }
+
+    /// Links `item` as a leaf with a fresh priority and rotates it up until
+    /// its parent outranks it.
+    pub fn insert(self: Pin<&mut Self>, item: A::Pointer) -> Option<A::Pointer> {
+        let item = item.into_raw();
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        self_.link.assert_unlinked::<TreeLink<A>>(item);
+        let key = A::get_key(unsafe { item.as_ref() });
+        let slot = match self_
+            .link
+            .search::<TreeLink<A>, A::Key, C>(key, &self_.compare)
+        {
+            Ok(_) => return Some(unsafe { A::Pointer::from_raw(item) }),
+            Err(slot) => slot,
+        };
+        let priority = (self_.rng.next() >> 32) as u32;
+        A::link_ref(unsafe { item.as_ref() }).priority.set(priority);
+        self_.link.link_leaf::<TreeLink<A>>(&slot, item);
+        self_.size.incr();
+        while let Some(top) = bintree::Link::of::<TreeLink<A>>(item).top() {
+            if Self::priority(top) >= priority {
+                break;
+            }
+            self_.rotate_up(top, item);
+        }
+        None
+    }
+
+    /// Rotates the element for `key` down below its higher-ranked children
+    /// until it has at most one, then unlinks it.
+    pub fn remove<Q>(self: Pin<&mut Self>, key: &Q) -> Option<A::Pointer>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_
+            .link
+            .search::<TreeLink<A>, Q, C>(key, &self_.compare)
+            .ok()?;
+        loop {
+            let link = bintree::Link::of::<TreeLink<A>>(node);
+            let (Some(left), Some(right)) = (link.left(), link.right()) else {
+                break;
+            };
+            if Self::priority(left) > Self::priority(right) {
+                self_.rotate_up(node, left);
+            } else {
+                self_.rotate_up(node, right);
+            }
+        }
+        Some(self_.unlink(node))
+    }
+
+    pub fn range<Q, R>(self: Pin<&Self>, range: R) -> Range<'_, T, A, P>
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let self_ = Pin::get_ref(self);
+        self_
+            .link
+            .range::<TreeLink<A>, Q, C, R>(range, &self_.compare)
+    }
+
+    /// Moves every element whose key is not less than `key` into the empty tree `other`.
+    ///
+    /// Nodes are relinked along a single path from the root, which keeps
+    /// both halves heap-ordered. A `LiterateSize` has to count the moved
+    /// elements, which makes the split O(k) in their number; with a
+    /// `NumerateSize` it takes O(height).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` is not empty.
+    pub fn split_off<Q>(self: Pin<&mut Self>, key: &Q, other: Pin<&mut Self>)
+    where
+        A::Key: Borrow<Q>,
+        C: Compare<Q>,
+        Q: ?Sized,
+    {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let other = unsafe { Pin::get_unchecked_mut(other) };
+        assert!(
+            other.link.top().is_none(),
+            "the tree to split into is not empty"
+        );
+        self_
+            .link
+            .split_off::<TreeLink<A>, Q, C>(&mut other.link, key, &self_.compare);
+
+        bintree::Link::move_size::<TreeLink<A>>(
+            &mut self_.size,
+            &mut other.size,
+            other.link.left(),
+        );
+    }
+
+    /// Moves every element of `other` into this tree, merging the right
+    /// spine of the lower tree with the left spine of the higher one by
+    /// priority. This takes O(height), as the sizes move over without a walk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys of one tree are not all less than those of the other.
+    pub fn append(self: Pin<&mut Self>, other: Pin<&mut Self>) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let other = unsafe { Pin::get_unchecked_mut(other) };
+        let (Some(other_first), Some(other_last)) = (other.link.left(), other.link.right()) else {
+            return;
+        };
+        let (Some(first), Some(last)) = (self_.link.left(), self_.link.right()) else {
+            // the headers stay put, as the pointers of their nodes may be
+            // relative to them; only the root is handed over
+            let top = other.link.top().unwrap();
+            other.link.unlink();
+            self_.link.hang::<TreeLink<A>>(None, top, true);
+            self_.link.set_ends(Some(other_first), Some(other_last));
+            mem::swap(&mut self_.size, &mut other.size);
+            return;
+        };
+        let key = |node: NonNull<T>| A::get_key(unsafe { node.as_ref() });
+        let is_less = |a, b| self_.compare.compare(key(a), key(b)) == cmp::Ordering::Less;
+        let (lower, higher, ends) = if is_less(last, other_first) {
+            (self_.link.top(), other.link.top(), (first, other_last))
+        } else if is_less(other_last, first) {
+            (other.link.top(), self_.link.top(), (other_first, last))
+        } else {
+            panic!("the keys of the appended tree overlap");
+        };
+
+        other
+            .link
+            .move_all_size::<TreeLink<A>>(&mut other.size, &mut self_.size);
+        other.link.unlink();
+
+        // each step hangs the higher-ranked of the two spine nodes at the
+        // current place, then descends into the side it leaves open
+        let (mut lower, mut higher) = (lower, higher);
+        let mut top = None;
+        let mut is_left = true;
+        loop {
+            let (node, next_is_left) = match (lower, higher) {
+                (Some(low), Some(high)) if Self::priority(low) >= Self::priority(high) => {
+                    lower = bintree::Link::of::<TreeLink<A>>(low).right();
+                    (low, false)
+                }
+                (Some(_), Some(high)) => {
+                    higher = bintree::Link::of::<TreeLink<A>>(high).left();
+                    (high, true)
+                }
+                (Some(rest), None) | (None, Some(rest)) => {
+                    self_.link.hang::<TreeLink<A>>(top, rest, is_left);
+                    break;
+                }
+                (None, None) => break,
+            };
+            self_.link.hang::<TreeLink<A>>(top, node, is_left);
+            top = Some(node);
+            is_left = next_is_left;
+        }
+        self_.link.set_ends(Some(ends.0), Some(ends.1));
+    }
+}
+
+impl<T, A, P, C> Treap<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn priority(node: NonNull<T>) -> u32 {
+        A::link_ref(unsafe { node.as_ref() }).priority.get()
+    }
+
+    /// Lifts `child` into the place of its parent `node`.
+    fn rotate_up(&mut self, node: NonNull<T>, child: NonNull<T>) {
+        if bintree::Link::of::<TreeLink<A>>(node).left() == Some(child) {
+            self.link.rotate_right::<TreeLink<A>>(node);
+        } else {
+            self.link.rotate_left::<TreeLink<A>>(node);
+        }
+    }
+
+    /// Unlinks `node`, which has at most one child, so the heap order holds.
+    fn unlink(&mut self, node: NonNull<T>) -> A::Pointer {
+        self.link.unlink_node::<TreeLink<A>>(node);
+        self.size.decr();
+        unsafe { A::Pointer::from_raw(node) }
+    }
+
+    pub fn pop_front(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.left()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn pop_back(self: Pin<&mut Self>) -> Option<A::Pointer> {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        let node = self_.link.right()?;
+        Some(self_.unlink(node))
+    }
+
+    pub fn front(self: Pin<&Self>) -> Option<&T> {
+        Pin::get_ref(self).link.front()
+    }
+
+    pub fn front_mut(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.front_mut()
+    }
+
+    pub fn back(self: Pin<&Self>) -> Option<&T> {
+        Pin::get_ref(self).link.back()
+    }
+
+    pub fn back_mut(self: Pin<&mut Self>) -> Option<&mut T>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.back_mut()
+    }
+
+    pub fn iter(self: Pin<&Self>) -> Iter<'_, T, A, P> {
+        Pin::get_ref(self).link.iter()
+    }
+
+    pub fn iter_mut(self: Pin<&mut Self>) -> IterMut<'_, T, A, P>
+    where
+        A::Pointer: UniquePointer<T>,
+    {
+        unsafe { Pin::get_unchecked_mut(self) }.link.iter_mut()
+    }
+
+    pub fn len(self: Pin<&Self>) -> usize {
+        self.link.len::<TreeLink<A>>(&self.size)
+    }
+
+    pub fn is_empty(self: Pin<&Self>) -> bool {
+        self.link.is_empty::<TreeLink<A>>(&self.size)
+    }
+
+    /// Unlinks every element, releasing the owned ones.
+    pub fn clear(self: Pin<&mut Self>) {
+        let self_ = unsafe { Pin::get_unchecked_mut(self) };
+        self_.link.clear::<TreeLink<A>>();
+        self_.size = Default::default();
+    }
+}
+
+impl<T, A, P, C> Default for Treap<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>> + Default,
+    C: Default,
+{
+    fn default() -> Self {
+        Self::with_comparator(A::default(), C::default())
+    }
+}
+
+impl<T, A, P, C> Drop for Treap<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+    fn drop(&mut self) {
+        if mem::needs_drop::<A::Pointer>() {
+            unsafe { Pin::new_unchecked(self) }.clear();
+        }
+    }
+}
+
+impl<T, A, P, C> Unpin for Treap<T, A, P, C>
+where
+    T: Unpin,
+    P: Pointer<T> + Unpin,
+    A: LinkAdapter<T, Link = Link<T, P>>,
+{
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::adapter::LiterateSize;
+    use crate::tree_test::{self, tree_fixture};
+
+    tree_fixture!(LiterateSize);
+
+    type Tree = Treap<X, XLink>;
+
+    /// Checks the links, the key order and the heap order, returning the height.
+    fn check(node: Option<NonNull<X>>, top: Option<NonNull<X>>) -> usize {
+        tree_test::check::<X, TreeLink<XLink>, _>(node, top, &mut |node| {
+            let link = bintree::Link::of::<TreeLink<XLink>>(node);
+            for child in [link.left(), link.right()].into_iter().flatten() {
+                assert!(Tree::priority(child) <= Tree::priority(node));
+            }
+        })
+    }
+
+    fn filled(seed: u64, keys: impl IntoIterator<Item = i32>) -> Pin<Box<Tree>> {
+        let mut tree = Box::pin(Treap::with_seed(XLink, seed));
+        for i in keys {
+            assert!(tree.as_mut().insert(X::new(i)).is_none());
+        }
+        tree
+    }
+
+    #[test]
+    fn test_insert_in_order() {
+        let mut tree = filled(1, 0..1000);
+        let height = check(tree.link.top(), None);
+        assert!(height < 40, "height {height}");
+        assert_eq!(data(tree.as_ref()), (0..1000).collect::<Vec<_>>());
+        assert_eq!(tree.as_ref().len(), 1000);
+        assert_eq!(tree.as_ref().front().unwrap().data, 0);
+        assert_eq!(tree.as_ref().back().unwrap().data, 999);
+
+        let item = tree.as_mut().insert(X::new(10)).unwrap();
+        assert_eq!(item.data, 10);
+        assert_eq!(tree.as_ref().get(&10).unwrap().data, 10);
+        assert!(tree.as_ref().get(&1000).is_none());
+        assert_eq!(Treap::get_mut(tree.as_mut(), &999).unwrap().data, 999);
+    }
+
+    #[test]
+    fn test_seed() {
+        let shape = |seed| {
+            let tree = filled(seed, [5, 2, 8, 1, 9, 3, 7, 4, 6]);
+            let priorities = tree
+                .as_ref()
+                .iter()
+                .map(|x| x.link.priority.get())
+                .collect::<Vec<_>>();
+            (
+                tree.link.top().map(|x| unsafe { x.as_ref() }.data),
+                priorities,
+            )
+        };
+        assert_eq!(shape(42), shape(42));
+        assert_ne!(shape(42).1, shape(43).1);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut tree = filled(7, (0..100).map(|i| i * 7 % 100));
+        for i in (0..100).step_by(3) {
+            assert_eq!(tree.as_mut().remove(&i).unwrap().data, i);
+            check(tree.link.top(), None);
+        }
+        assert!(tree.as_mut().remove(&0).is_none());
+        assert_eq!(tree.as_ref().len(), 66);
+        assert_eq!(tree.as_mut().pop_front().unwrap().data, 1);
+        assert_eq!(tree.as_mut().pop_back().unwrap().data, 98);
+        check(tree.link.top(), None);
+        assert_eq!(
+            tree.as_ref()
+                .range(10..15)
+                .map(|x| x.data)
+                .collect::<Vec<_>>(),
+            [10, 11, 13, 14]
+        );
+        tree.as_mut().clear();
+        assert!(tree.as_ref().is_empty());
+    }
+
+    #[test]
+    fn test_split_append() {
+        let mut tree = filled(3, 0..50);
+        let mut other = filled(4, []);
+        tree.as_mut().split_off(&20, other.as_mut());
+        check(tree.link.top(), None);
+        check(other.link.top(), None);
+        assert_eq!(data(tree.as_ref()), (0..20).collect::<Vec<_>>());
+        assert_eq!(data(other.as_ref()), (20..50).collect::<Vec<_>>());
+        assert_eq!((tree.as_ref().len(), other.as_ref().len()), (20, 30));
+
+        let mut rest = filled(5, []);
+        other.as_mut().split_off(&35, rest.as_mut());
+        tree.as_mut().append(rest.as_mut());
+        assert!(rest.as_ref().is_empty());
+        check(tree.link.top(), None);
+        assert_eq!(
+            data(tree.as_ref()),
+            (0..20).chain(35..50).collect::<Vec<_>>()
+        );
+
+        let mut high = filled(6, []);
+        tree.as_mut().split_off(&35, high.as_mut());
+        other.as_mut().append(high.as_mut());
+        other.as_mut().append(tree.as_mut());
+        check(other.link.top(), None);
+        assert_eq!(data(other.as_ref()), (0..50).collect::<Vec<_>>());
+        assert_eq!(other.as_ref().front().unwrap().data, 0);
+        assert_eq!(other.as_ref().back().unwrap().data, 49);
+        assert_eq!(other.as_ref().len(), 50);
+
+        tree.as_mut().append(other.as_mut());
+        assert!(other.as_ref().is_empty());
+        assert_eq!(data(tree.as_ref()), (0..50).collect::<Vec<_>>());
+        assert_eq!(tree.as_ref().len(), 50);
+        check(tree.link.top(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "overlap")]
+    fn test_append_overlap() {
+        let mut tree = filled(1, [1, 3]);
+        let mut other = filled(1, [2]);
+        tree.as_mut().append(other.as_mut());
+    }
+}
@@ -0,0 +1,68 @@
+//! Fixtures shared by the tests of the search trees built on `bintree::Link`.
+
+use crate::adapter::KeyAdapter;
+use crate::bintree::Link;
+use crate::ptr::Pointer;
+use std::ptr::NonNull;
+
+/// Declares the element `X`, keyed by its `data`, its adapter `XLink` over
+/// the `Link` in scope, owning through `Box<X>` and counting with `$size`,
+/// and `data`, which lists the keys of a `Tree` in order.
+macro_rules! tree_fixture {
+    ($size:ty) => {
+        struct X {
+            data: i32,
+            link: Link<Self>,
+        }
+
+        impl X {
+            fn new(data: i32) -> Box<Self> {
+                Box::new(X {
+                    data,
+                    link: Link::new(),
+                })
+            }
+        }
+
+        $crate::intrusive_adapter! {
+            XLink = X { link: Link<X>, key = data: i32 } with $size, Box<X>;
+        }
+
+        fn data(tree: std::pin::Pin<&Tree>) -> Vec<i32> {
+            tree.iter().map(|x| x.data).collect()
+        }
+    };
+}
+
+pub(crate) use tree_fixture;
+
+/// Checks the parent links and the key order below `node`, handing every
+/// node to `visit`, and returns the number of nodes on the longest path.
+pub(crate) fn check<T, A, P>(
+    node: Option<NonNull<T>>,
+    top: Option<NonNull<T>>,
+    visit: &mut impl FnMut(NonNull<T>),
+) -> usize
+where
+    T: Unpin,
+    P: Pointer<T>,
+    A: KeyAdapter<T, Link = Link<T, P>>,
+    A::Key: Ord,
+{
+    let Some(node) = node else {
+        return 0;
+    };
+    let link = Link::of::<A>(node);
+    assert_eq!(link.top(), top);
+    let key = |node: NonNull<T>| A::get_key(unsafe { node.as_ref() });
+    if let Some(left) = link.left() {
+        assert!(key(left) < key(node));
+    }
+    if let Some(right) = link.right() {
+        assert!(key(right) > key(node));
+    }
+    visit(node);
+    let left = check::<T, A, P>(link.left(), Some(node), visit);
+    let right = check::<T, A, P>(link.right(), Some(node), visit);
+    1 + left.max(right)
+}